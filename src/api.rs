@@ -0,0 +1,222 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! The JSON API of the model server, independent of the HTTP transport.
+//!
+//! - `POST /move` with `{"board": "<board_state>", "side": "X"}` returns the move the agent
+//!   plays, as `{"move": [row, col], "action": "row,col", "moves": [...]}`.
+//! - `POST /evaluate` with the same body returns the position's status, the side to move,
+//!   its value and every legal move with its Q-value and visit count, without choosing one.
+//! - `GET /model` returns the model header.
+//!
+//! `side` picks who moves when both have played equally often, X by default; otherwise it is
+//! optional and only checked against the marker counts. Moves the agent has never learned
+//! have a `q` of `null`. Boards must have the size the model was trained on.
+
+use crate::model::ModelHeader;
+use crate::{Board, Cell, QLearningAgent};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+#[derive(Debug, Deserialize)]
+struct PositionRequest {
+    board: String,
+    side: Option<String>,
+}
+
+/// Serves one request, returning the HTTP status code and JSON body of the reply.
+pub fn handle(agent: &mut QLearningAgent, header: &ModelHeader, method: &str, path: &str, body: &str) -> (u16, Value) {
+    let path = path.split_once('?').map_or(path, |(path, _)| path);
+    let reply = match (method, path) {
+        ("POST", "/move") => position(body, header).and_then(|board| choose(agent, &board)),
+        ("POST", "/evaluate") => position(body, header).map(|board| evaluate(agent, &board)),
+        ("GET", "/model") => serde_json::to_value(header).map_err(|err| (500, err.to_string())),
+        (_, "/move" | "/evaluate" | "/model") => Err((405, format!("{} is not allowed on {}", method, path))),
+        _ => Err((404, format!("no such endpoint {}", path))),
+    };
+    match reply {
+        Ok(body) => (200, body),
+        Err((status, error)) => (status, json!({ "error": error })),
+    }
+}
+
+fn position(body: &str, header: &ModelHeader) -> Result<Board, (u16, String)> {
+    let request: PositionRequest = serde_json::from_str(body).map_err(|err| (400, format!("bad request body: {}", err)))?;
+    let mut board =
+        Board::from_state_with_size(header.board, &request.board).ok_or_else(|| {
+            (400, format!("invalid board state '{}' for a {} board", request.board, header.board))
+        })?;
+    let side = match request.side.as_deref().map(str::to_uppercase).as_deref() {
+        None => None,
+        Some("X") => Some(Cell::X),
+        Some("O") => Some(Cell::O),
+        Some(other) => return Err((400, format!("unknown side '{}'", other))),
+    };
+    let x_count = request.board.matches('X').count();
+    if x_count == request.board.matches('O').count() {
+        board.set_current_player(side.unwrap_or(Cell::X));
+    } else if side.is_some_and(|side| side != board.get_current_player().marker) {
+        return Err((400, format!("it is {}'s turn in {}", board.get_current_player().marker, request.board)));
+    }
+    Ok(board)
+}
+
+fn choose(agent: &mut QLearningAgent, board: &Board) -> Result<Value, (u16, String)> {
+    if board.is_game_over().0 {
+        return Err((400, "the game is over".to_string()));
+    }
+    let state = board.board_state();
+    let ((row, col), _, _) = agent.choose_action(&state, &board.available_moves(), board.find_blocking_move());
+    Ok(json!({
+        "move": [row, col],
+        "action": format!("{},{}", row, col),
+        "side": board.get_current_player().marker.to_string(),
+        "moves": moves(agent, board),
+    }))
+}
+
+fn evaluate(agent: &QLearningAgent, board: &Board) -> Value {
+    let status = match board.is_game_over() {
+        (_, Some(Cell::X)) => "x_wins",
+        (_, Some(_)) => "o_wins",
+        (true, None) => "draw",
+        (false, None) => "in_progress",
+    };
+    let state = board.board_state();
+    let value = agent
+        .q_table
+        .get(&state)
+        .and_then(|actions| actions.values().map(|entry| entry.value).max_by(f64::total_cmp));
+    json!({
+        "board": state,
+        "status": status,
+        "side": board.get_current_player().marker.to_string(),
+        "value": value,
+        "greedy": agent.greedy_action(&state, &board.available_moves()).map(|(row, col)| [row, col]),
+        "moves": moves(agent, board),
+    })
+}
+
+/// The legal moves with what the agent knows about them; none once the game is over.
+fn moves(agent: &QLearningAgent, board: &Board) -> Vec<Value> {
+    if board.is_game_over().0 {
+        return Vec::new();
+    }
+    let actions = agent.q_table.get(&board.board_state());
+    board
+        .available_moves()
+        .into_iter()
+        .map(|(row, col)| {
+            let entry = actions.and_then(|actions| actions.get(&format!("{},{}", row, col)));
+            json!({
+                "move": [row, col],
+                "q": entry.map(|entry| entry.value),
+                "visits": entry.map_or(0, |entry| entry.visits),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::VERSION;
+    use crate::QEntry;
+
+    /// An agent that prefers the centre of the empty board and knows one other position.
+    fn agent() -> (QLearningAgent, ModelHeader) {
+        let mut agent = QLearningAgent::new(0.1, 0.9, 0.5);
+        agent.train = false;
+        let entries = [("---------", "1,1", 0.5, 7), ("---------", "0,0", 0.2, 3), ("X--------", "1,1", 0.4, 2)];
+        for (state, action, value, visits) in entries {
+            let entry = QEntry { value, visits, ..QEntry::default() };
+            agent.q_table.entry(state.to_string()).or_default().insert(action.to_string(), entry);
+        }
+        let header = ModelHeader::describe(&agent, VERSION, 0);
+        (agent, header)
+    }
+
+    fn request(method: &str, path: &str, body: &str) -> (u16, Value) {
+        let (mut agent, header) = agent();
+        handle(&mut agent, &header, method, path, body)
+    }
+
+    #[test]
+    fn move_plays_the_greedy_move() {
+        let (status, reply) = request("POST", "/move", r#"{"board": "---------"}"#);
+        assert_eq!(status, 200);
+        assert_eq!(reply["move"], json!([1, 1]));
+        assert_eq!(reply["action"], "1,1");
+        assert_eq!(reply["side"], "X");
+        assert_eq!(reply["moves"].as_array().unwrap().len(), 9);
+
+        let (status, reply) = request("POST", "/move", r#"{"board": "X--------"}"#);
+        assert_eq!(status, 200);
+        assert_eq!(reply["side"], "O");
+        assert_eq!(reply["move"], json!([1, 1]));
+    }
+
+    #[test]
+    fn move_is_refused_once_the_game_is_over() {
+        let (status, reply) = request("POST", "/move", r#"{"board": "XXXOO----"}"#);
+        assert_eq!(status, 400);
+        assert_eq!(reply["error"], "the game is over");
+    }
+
+    #[test]
+    fn evaluate_reports_values_without_choosing() {
+        let (status, reply) = request("POST", "/evaluate", r#"{"board": "---------", "side": "o"}"#);
+        assert_eq!(status, 200);
+        assert_eq!(reply["status"], "in_progress");
+        assert_eq!(reply["side"], "O");
+        assert_eq!(reply["value"], 0.5);
+        assert_eq!(reply["greedy"], json!([1, 1]));
+        let moves = reply["moves"].as_array().unwrap();
+        let centre = moves.iter().find(|entry| entry["move"] == json!([1, 1])).unwrap();
+        assert_eq!(centre["q"], 0.5);
+        assert_eq!(centre["visits"], 7);
+        let corner = moves.iter().find(|entry| entry["move"] == json!([2, 2])).unwrap();
+        assert!(corner["q"].is_null());
+        assert_eq!(corner["visits"], 0);
+
+        let (_, reply) = request("POST", "/evaluate", r#"{"board": "XXXOO----"}"#);
+        assert_eq!(reply["status"], "x_wins");
+        assert!(reply["value"].is_null());
+        assert_eq!(reply["moves"], json!([]));
+    }
+
+    #[test]
+    fn model_returns_the_header() {
+        let (status, reply) = request("GET", "/model?pretty", "");
+        assert_eq!(status, 200);
+        assert_eq!(reply["algorithm"], "tabular-q-learning");
+        assert_eq!(reply["states"], 2);
+        assert_eq!(reply["board"], json!({ "width": 3, "height": 3, "k": 3 }));
+    }
+
+    #[test]
+    fn unknown_paths_and_methods_are_rejected() {
+        assert_eq!(request("GET", "/nowhere", "").0, 404);
+        assert_eq!(request("GET", "/move", "").0, 405);
+        assert_eq!(request("POST", "/model", "").0, 405);
+        let (status, reply) = request("DELETE", "/evaluate", "");
+        assert_eq!(status, 405);
+        assert_eq!(reply["error"], "DELETE is not allowed on /evaluate");
+    }
+
+    #[test]
+    fn bad_bodies_are_rejected() {
+        for body in ["", "not json", r#"{"side": "X"}"#, r#"{"board": "XXXX-----"}"#, r#"{"board": "---"}"#] {
+            let (status, reply) = request("POST", "/move", body);
+            assert_eq!(status, 400, "{}", body);
+            assert!(reply["error"].is_string());
+        }
+        let (status, reply) = request("POST", "/evaluate", r#"{"board": "---------", "side": "Z"}"#);
+        assert_eq!((status, reply["error"].as_str().unwrap()), (400, "unknown side 'Z'"));
+        let (status, reply) = request("POST", "/evaluate", r#"{"board": "X--------", "side": "X"}"#);
+        assert_eq!((status, reply["error"].as_str().unwrap()), (400, "it is O's turn in X--------"));
+    }
+}
@@ -0,0 +1,58 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! `server [--model FILE] [--bind ADDRESS]` serves a saved agent over HTTP; see `api` for the
+//! endpoints. It listens on 127.0.0.1:8080 unless told otherwise, and the agent never
+//! explores.
+
+use q_learning_tictactoe::rules::Rules;
+use q_learning_tictactoe::{api, model, QLearningAgent, FILENAME};
+use tiny_http::{Header, Response, Server};
+
+fn main() {
+    if let Err(err) = run(&std::env::args().skip(1).collect::<Vec<_>>()) {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut path = FILENAME.to_string();
+    let mut bind = "127.0.0.1:8080".to_string();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--model" => path = value()?.clone(),
+            "--bind" => bind = value()?.clone(),
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
+    let header = model::read_header(&path)?;
+    if header.rules != Rules::Standard {
+        return Err(format!("{} was trained for {} rules; only standard games can be served", path, header.rules).into());
+    }
+    let mut agent = QLearningAgent::load_from_file(&path)?;
+    agent.set_training(false);
+
+    let server = Server::http(&bind).map_err(|err| format!("cannot listen on {}: {}", bind, err))?;
+    println!("Serving {} on http://{}", path, server.server_addr());
+    let content_type = Header::from_bytes("Content-Type", "application/json").expect("valid header");
+    for mut request in server.incoming_requests() {
+        let mut body = String::new();
+        let (status, reply) = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) => api::handle(&mut agent, &header, request.method().as_str(), request.url(), &body),
+            Err(err) => (400, serde_json::json!({ "error": err.to_string() })),
+        };
+        let response = Response::from_string(reply.to_string())
+            .with_status_code(status)
+            .with_header(content_type.clone());
+        if let Err(err) = request.respond(response) {
+            eprintln!("Failed to reply: {}", err);
+        }
+    }
+    Ok(())
+}
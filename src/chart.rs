@@ -0,0 +1,178 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+use crate::metrics::MetricsRow;
+use iced::mouse;
+use iced::widget::canvas::{self, Frame, Geometry, LineDash, Path, Stroke, Text};
+use iced::{Color, Point, Rectangle, Renderer, Size, Theme};
+
+const WIN_COLOR: Color = Color::from_rgb(0.2, 0.7, 0.3);
+const DRAW_COLOR: Color = Color::from_rgb(0.3, 0.5, 0.9);
+const LOSS_COLOR: Color = Color::from_rgb(0.85, 0.3, 0.3);
+const TABLE_COLOR: Color = Color::from_rgb(0.9, 0.6, 0.1);
+/// The dash pattern of the rates of games played as O.
+const O_DASH: [f32; 2] = [2.0, 3.0];
+/// Curves of previous runs are drawn faded behind the current one.
+const OVERLAY_ALPHA: f32 = 0.3;
+
+const MARGIN_LEFT: f32 = 32.0;
+const MARGIN_RIGHT: f32 = 8.0;
+const MARGIN_TOP: f32 = 8.0;
+const MARGIN_BOTTOM: f32 = 40.0;
+
+/// Win, draw and loss rates as either side and Q-table growth over the episodes of a training
+/// run, with the curves of previously saved runs overlaid.
+pub struct LearningCurves<'a> {
+    pub current: &'a [MetricsRow],
+    /// The number of episodes the current run will play, so its curves grow from the left.
+    pub episodes: usize,
+    pub overlays: &'a [(String, Vec<MetricsRow>)],
+}
+
+/// The share of the games the agent played as each side that it won, drew and lost, X first.
+/// The sides are kept apart because in self-play every decisive game is a win for one and a
+/// loss for the other, so summed over both the win and loss rates would always be equal.
+fn rates(row: &MetricsRow) -> [[f32; 3]; 2] {
+    let side = |wins: usize, draws: usize, losses: usize| {
+        let games = (wins + draws + losses).max(1) as f32;
+        [wins as f32 / games, draws as f32 / games, losses as f32 / games]
+    };
+    [side(row.x_wins, row.x_draws, row.x_losses), side(row.o_wins, row.o_draws, row.o_losses)]
+}
+
+impl LearningCurves<'_> {
+    fn all_rows(&self) -> impl Iterator<Item = &MetricsRow> {
+        self.current.iter().chain(self.overlays.iter().flat_map(|(_, rows)| rows.iter()))
+    }
+}
+
+impl<Message> canvas::Program<Message> for LearningCurves<'_> {
+    type State = ();
+
+    fn draw(&self, _state: &(), renderer: &Renderer, theme: &Theme, bounds: Rectangle, _cursor: mouse::Cursor) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let text_color = theme.palette().text;
+        let plot = Rectangle {
+            x: MARGIN_LEFT,
+            y: MARGIN_TOP,
+            width: (bounds.width - MARGIN_LEFT - MARGIN_RIGHT).max(1.0),
+            height: (bounds.height - MARGIN_TOP - MARGIN_BOTTOM).max(1.0),
+        };
+        let max_episode = self.all_rows().map(|row| row.episode).max().unwrap_or(0).max(self.episodes).max(1);
+        let max_table = self.all_rows().map(|row| row.q_table_size).max().unwrap_or(0).max(1);
+        let to_point = |episode: usize, value: f32| {
+            Point::new(
+                plot.x + plot.width * episode as f32 / max_episode as f32,
+                plot.y + plot.height * (1.0 - value),
+            )
+        };
+
+        let axis = Stroke::default().with_color(Color { a: 0.5, ..text_color }).with_width(1.0);
+        frame.stroke(&Path::rectangle(Point::new(plot.x, plot.y), Size::new(plot.width, plot.height)), axis.clone());
+        frame.stroke(&Path::line(to_point(0, 0.5), to_point(max_episode, 0.5)), Stroke { line_dash: LineDash { segments: &[3.0, 3.0], offset: 0 }, ..axis });
+        for (label, value, y_offset) in [("1.0", 1.0, 0.0), ("0.5", 0.5, -6.0), ("0.0", 0.0, -12.0)] {
+            let position = to_point(0, value);
+            frame.fill_text(Text {
+                content: label.to_string(),
+                position: Point::new(4.0, position.y + y_offset),
+                color: text_color,
+                size: 12.0.into(),
+                ..Text::default()
+            });
+        }
+        frame.fill_text(Text {
+            content: format!("episodes: {}    Q-table: up to {} states", max_episode, max_table),
+            position: Point::new(plot.x, plot.y + plot.height + 4.0),
+            color: text_color,
+            size: 12.0.into(),
+            ..Text::default()
+        });
+
+        let mut draw_run = |rows: &[MetricsRow], alpha: f32| {
+            for (side, segments) in [&[][..], &O_DASH[..]].into_iter().enumerate() {
+                for (series, color) in [WIN_COLOR, DRAW_COLOR, LOSS_COLOR].into_iter().enumerate() {
+                    let curve = Path::new(|builder| {
+                        for (i, row) in rows.iter().enumerate() {
+                            let point = to_point(row.episode, rates(row)[side][series]);
+                            if i == 0 { builder.move_to(point) } else { builder.line_to(point) }
+                        }
+                    });
+                    frame.stroke(
+                        &curve,
+                        Stroke {
+                            line_dash: LineDash { segments, offset: 0 },
+                            ..Stroke::default().with_color(Color { a: alpha, ..color }).with_width(2.0)
+                        },
+                    );
+                }
+            }
+            let growth = Path::new(|builder| {
+                for (i, row) in rows.iter().enumerate() {
+                    let point = to_point(row.episode, row.q_table_size as f32 / max_table as f32);
+                    if i == 0 { builder.move_to(point) } else { builder.line_to(point) }
+                }
+            });
+            frame.stroke(
+                &growth,
+                Stroke {
+                    line_dash: LineDash { segments: &[6.0, 4.0], offset: 0 },
+                    ..Stroke::default().with_color(Color { a: alpha, ..TABLE_COLOR }).with_width(2.0)
+                },
+            );
+        };
+        for (_, rows) in self.overlays {
+            draw_run(rows, OVERLAY_ALPHA);
+        }
+        draw_run(self.current, 1.0);
+
+        let mut x = plot.x;
+        let legend = [
+            ("win", WIN_COLOR),
+            ("draw", DRAW_COLOR),
+            ("loss", LOSS_COLOR),
+            ("X solid, O dotted", text_color),
+            ("Q-table size", TABLE_COLOR),
+        ];
+        for (label, color) in legend {
+            frame.fill_text(Text {
+                content: label.to_string(),
+                position: Point::new(x, plot.y + plot.height + 20.0),
+                color,
+                size: 12.0.into(),
+                ..Text::default()
+            });
+            x += 8.0 * label.len() as f32 + 16.0;
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_are_per_side() {
+        // 100 self-play games: X won 60, O won 10 and 30 were drawn
+        let row = MetricsRow {
+            episode: 100,
+            epsilon: 0.5,
+            alpha: 0.1,
+            x_wins: 60,
+            x_draws: 30,
+            x_losses: 10,
+            o_wins: 10,
+            o_draws: 30,
+            o_losses: 60,
+            avg_game_length: 7.0,
+            q_table_size: 1000,
+            mean_abs_dq: 0.01,
+            blocking_move_frequency: 0.1,
+        };
+        assert_eq!(rates(&row), [[0.6, 0.3, 0.1], [0.1, 0.3, 0.6]]);
+    }
+}
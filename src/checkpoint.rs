@@ -0,0 +1,87 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+use crate::QLearningAgent;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Saves the agent every `interval` episodes of a training run, keeping only the newest
+/// `keep` checkpoints.
+///
+/// A checkpoint of model `out/model.json` after 5000 episodes is `out/model-5000.json`, in the
+/// same format as the model. Checkpoints left by an earlier run of the same model count
+/// towards `keep`, so a resumed run cleans up after the interrupted one.
+#[derive(Debug)]
+pub struct Checkpoints {
+    pub interval: usize,
+    pub keep: usize,
+    directory: PathBuf,
+    stem: String,
+    extension: String,
+    /// Saved checkpoints by episode, oldest first.
+    saved: Vec<(usize, PathBuf)>,
+}
+
+impl Checkpoints {
+    pub fn new(output: &str, interval: usize, keep: usize) -> Self {
+        let output = Path::new(output);
+        let directory = match output.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let stem = output.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        let extension = output.extension().map_or_else(String::new, |extension| extension.to_string_lossy().into_owned());
+        let mut checkpoints = Checkpoints { interval, keep, directory, stem, extension, saved: Vec::new() };
+        checkpoints.saved = checkpoints.existing();
+        checkpoints
+    }
+
+    /// Saves a checkpoint if `episodes_played` falls on the interval.
+    pub fn save(&mut self, agent: &QLearningAgent, episodes_played: usize) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+        if self.interval == 0 || !episodes_played.is_multiple_of(self.interval) {
+            return Ok(None);
+        }
+        let path = self.path(episodes_played);
+        agent.save_to_path(&path.to_string_lossy())?;
+        self.saved.retain(|(episode, _)| *episode != episodes_played);
+        self.saved.push((episodes_played, path.clone()));
+        while self.saved.len() > self.keep.max(1) {
+            let (_, oldest) = self.saved.remove(0);
+            fs::remove_file(&oldest).map_err(|err| format!("{}: {}", oldest.display(), err))?;
+        }
+        Ok(Some(path))
+    }
+
+    fn path(&self, episodes_played: usize) -> PathBuf {
+        let mut name = format!("{}-{}", self.stem, episodes_played);
+        if !self.extension.is_empty() {
+            name = format!("{}.{}", name, self.extension);
+        }
+        self.directory.join(name)
+    }
+
+    /// Checkpoints of this model already on disk, oldest first.
+    fn existing(&self) -> Vec<(usize, PathBuf)> {
+        let Ok(entries) = fs::read_dir(&self.directory) else {
+            return Vec::new();
+        };
+        let mut existing: Vec<_> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let name = path.file_name()?.to_str()?;
+                let name = if self.extension.is_empty() {
+                    name
+                } else {
+                    name.strip_suffix(&format!(".{}", self.extension))?
+                };
+                let episode = name.strip_prefix(&format!("{}-", self.stem))?.parse().ok()?;
+                Some((episode, path))
+            })
+            .collect();
+        existing.sort();
+        existing
+    }
+}
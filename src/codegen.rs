@@ -0,0 +1,153 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! Generates source code holding a trained agent's policy as a constant lookup table.
+//!
+//! The table has an entry for each of the 3^9 boards, indexed by the board read as a base-3
+//! number with empty, X and O as the digits 0, 1 and 2. Each entry is the cell of the greedy
+//! move, `row * 3 + col`, or 255 for boards that cannot come up in play or are already over.
+//! The generated code needs neither `std` nor `libc`.
+
+use crate::curriculum::reachable_positions;
+use crate::{model, Board, QLearningAgent};
+use std::fmt::Write;
+use std::path::Path;
+
+const NO_MOVE: u8 = 255;
+const ENTRIES: usize = 19683;
+const PER_LINE: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Language {
+    Rust,
+    C,
+}
+
+impl Language {
+    /// Parses `rust` or `c`.
+    pub fn parse(spec: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        match spec {
+            "rust" => Ok(Language::Rust),
+            "c" => Ok(Language::C),
+            _ => Err(format!("unknown language '{}'", spec).into()),
+        }
+    }
+
+    /// C for `.h` and `.c` files, Rust otherwise.
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|extension| extension.to_str()) {
+            Some("h" | "c") => Language::C,
+            _ => Language::Rust,
+        }
+    }
+}
+
+/// The generated table, and how many reachable positions the agent had never seen. Those
+/// fall back to the first empty cell.
+pub struct Policy {
+    pub moves: Vec<u8>,
+    pub unknown: usize,
+}
+
+pub fn policy(agent: &QLearningAgent) -> Policy {
+    let mut policy = Policy { moves: vec![NO_MOVE; ENTRIES], unknown: 0 };
+    for state in std::iter::once(Board::new().board_state()).chain(reachable_positions()) {
+        let Some(board) = Board::from_state(&state) else { continue };
+        let moves = board.available_moves();
+        let (row, col) = match agent.greedy_action(&state, &moves) {
+            Some(greedy) => greedy,
+            None => {
+                policy.unknown += 1;
+                moves[0]
+            }
+        };
+        let index = model::pack_state(&state).expect("reachable positions are valid states") as usize;
+        policy.moves[index] = (row * 3 + col) as u8;
+    }
+    policy
+}
+
+pub fn generate(policy: &Policy, language: Language, source: &str) -> String {
+    let mut code = String::new();
+    let rows: Vec<String> = policy
+        .moves
+        .chunks(PER_LINE)
+        .map(|chunk| chunk.iter().map(u8::to_string).collect::<Vec<_>>().join(", "))
+        .collect();
+    match language {
+        Language::Rust => {
+            let _ = writeln!(code, "// Generated by q-learning-tictactoe from {}. Do not edit.", source);
+            code.push_str(RUST_PRELUDE);
+            let _ = writeln!(code, "pub const POLICY: [u8; {}] = [", ENTRIES);
+            for row in rows {
+                let _ = writeln!(code, "    {},", row);
+            }
+            code.push_str("];\n");
+        }
+        Language::C => {
+            let _ = writeln!(code, "/* Generated by q-learning-tictactoe from {}. Do not edit. */", source);
+            code.push_str(C_PRELUDE);
+            let _ = writeln!(code, "static const unsigned char TICTACTOE_POLICY[{}] = {{", ENTRIES);
+            for row in rows {
+                let _ = writeln!(code, "    {},", row);
+            }
+            code.push_str("};\n");
+            code.push_str(C_LOOKUP);
+        }
+    }
+    code
+}
+
+const RUST_PRELUDE: &str = r#"
+/// `POLICY` entry of positions without a move.
+pub const NO_MOVE: u8 = 255;
+
+/// The index of a board in `POLICY`. Cells are given row by row as 0 (empty), 1 (X) or 2 (O).
+pub const fn state_index(cells: &[u8; 9]) -> usize {
+    let mut index = 0;
+    let mut i = 0;
+    while i < 9 {
+        index = index * 3 + cells[i] as usize;
+        i += 1;
+    }
+    index
+}
+
+/// The move to play as `(row, col)`, or `None` for positions that are over or unreachable.
+pub const fn best_move(cells: &[u8; 9]) -> Option<(usize, usize)> {
+    match POLICY[state_index(cells)] {
+        NO_MOVE => None,
+        cell => Some((cell as usize / 3, cell as usize % 3)),
+    }
+}
+
+/// The greedy move of every board, as `row * 3 + col`.
+"#;
+
+const C_PRELUDE: &str = r#"
+#ifndef TICTACTOE_POLICY_H
+#define TICTACTOE_POLICY_H
+
+/* TICTACTOE_POLICY entry of positions without a move. */
+#define TICTACTOE_NO_MOVE 255
+
+/* The greedy move of every board, as row * 3 + col. */
+"#;
+
+const C_LOOKUP: &str = r#"
+/* The cell to play, row * 3 + col, or -1 for positions that are over or unreachable.
+   Cells are given row by row as 0 (empty), 1 (X) or 2 (O). */
+static inline int tictactoe_best_move(const unsigned char cells[9]) {
+    unsigned int index = 0;
+    for (int i = 0; i < 9; i++) {
+        index = index * 3 + cells[i];
+    }
+    unsigned char cell = TICTACTOE_POLICY[index];
+    return cell == TICTACTOE_NO_MOVE ? -1 : cell;
+}
+
+#endif
+"#;
@@ -0,0 +1,236 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+use crate::opponent::Opponent;
+use crate::{random, Board, Cell, QLearningAgent};
+use rand::prelude::IndexedRandom;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Magnitudes of the Q-value changes, and the learning rates they were made with, since the
+/// window was last reset.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChangeWindow {
+    pub updates: u64,
+    pub total: f64,
+    pub max: f64,
+    pub alpha_total: f64,
+}
+
+impl ChangeWindow {
+    pub fn record(&mut self, change: f64, alpha: f64) {
+        let change = change.abs();
+        self.updates += 1;
+        self.total += change;
+        self.max = self.max.max(change);
+        self.alpha_total += alpha;
+    }
+
+    pub fn merge(&mut self, other: &ChangeWindow) {
+        self.updates += other.updates;
+        self.total += other.total;
+        self.max = self.max.max(other.max);
+        self.alpha_total += other.alpha_total;
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.updates == 0 { 0.0 } else { self.total / self.updates as f64 }
+    }
+
+    pub fn mean_alpha(&self) -> f64 {
+        if self.updates == 0 { 0.0 } else { self.alpha_total / self.updates as f64 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum StopReason {
+    /// Every episode was played.
+    Completed,
+    /// The Q-value changes of a window fell under the thresholds.
+    Converged,
+    /// Evaluation performance stopped improving.
+    Plateau,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Completed => write!(f, "completed all episodes"),
+            StopReason::Converged => write!(f, "Q-values converged"),
+            StopReason::Plateau => write!(f, "evaluation plateaued"),
+        }
+    }
+}
+
+/// How a training run ended, saved alongside the model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainingSummary {
+    pub episodes: usize,
+    pub stop_reason: StopReason,
+    pub max_change: f64,
+    pub mean_change: f64,
+    /// The last evaluation score against the reference opponent, if any was run.
+    pub evaluation: Option<f64>,
+    /// The share of the run's moves that explored, absent from older models.
+    #[serde(default)]
+    pub exploration: Option<f64>,
+}
+
+impl fmt::Display for TrainingSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Stopped after {} episodes ({}), last window max |dQ|: {:.6}, mean |dQ|: {:.6}",
+            self.episodes, self.stop_reason, self.max_change, self.mean_change
+        )?;
+        if let Some(score) = self.evaluation {
+            write!(f, ", evaluation score: {:.3}", score)?;
+        }
+        if let Some(exploration) = self.exploration {
+            write!(f, ", exploration: {:.2}", exploration)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Evaluation {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl Evaluation {
+    /// Wins count one point and draws half a point, averaged over the games played.
+    pub fn score(&self) -> f64 {
+        let games = self.wins + self.draws + self.losses;
+        (self.wins as f64 + 0.5 * self.draws as f64) / games.max(1) as f64
+    }
+}
+
+/// Plays `games` greedy games against `opponent` on the agent's board, alternating its side.
+pub fn evaluate(agent: &QLearningAgent, opponent: &mut Opponent, games: usize) -> Evaluation {
+    let mut result = Evaluation::default();
+    let mut rng = random::rng();
+    for game_index in 0..games {
+        let mut game = Board::with_size(agent.metadata.board);
+        let agent_marker = if game_index % 2 == 0 { Cell::X } else { Cell::O };
+        let winner = loop {
+            let (game_over, winner) = game.is_game_over();
+            if game_over {
+                break winner;
+            }
+            let action = if game.get_current_player().marker == agent_marker {
+                let moves = game.available_moves();
+                agent
+                    .greedy_action(&game.board_state(), &moves)
+                    .unwrap_or_else(|| *moves.choose(&mut rng).unwrap())
+            } else {
+                opponent.choose_move(&game)
+            };
+            game.make_move(action.0, action.1);
+        };
+        match winner {
+            Some(marker) if marker == agent_marker => result.wins += 1,
+            Some(_) => result.losses += 1,
+            None => result.draws += 1,
+        }
+    }
+    result
+}
+
+/// Criteria for ending training before all episodes have been played.
+///
+/// Q-value changes are summarised every `window` episodes; training stops once both the
+/// maximum and mean absolute change of a window fall under their thresholds. With an
+/// `evaluation_interval`, the greedy agent also plays `evaluation_games` against the reference
+/// opponent, and training stops when the score has not improved by `min_improvement` for
+/// `patience` evaluations in a row.
+#[derive(Debug)]
+pub struct EarlyStopping {
+    pub window: usize,
+    pub max_change: Option<f64>,
+    pub mean_change: f64,
+    pub opponent: Opponent,
+    pub evaluation_interval: Option<usize>,
+    pub evaluation_games: usize,
+    pub patience: usize,
+    pub min_improvement: f64,
+    best_score: f64,
+    stale_evaluations: usize,
+    last_score: Option<f64>,
+    changes: ChangeWindow,
+    last_window: ChangeWindow,
+}
+
+impl EarlyStopping {
+    pub fn new(opponent: Opponent) -> Self {
+        EarlyStopping {
+            window: 1000,
+            max_change: None,
+            mean_change: f64::INFINITY,
+            opponent,
+            evaluation_interval: None,
+            evaluation_games: 200,
+            patience: 5,
+            min_improvement: 0.005,
+            best_score: f64::NEG_INFINITY,
+            stale_evaluations: 0,
+            last_score: None,
+            changes: ChangeWindow::default(),
+            last_window: ChangeWindow::default(),
+        }
+    }
+
+    /// Never stops training, but still summarises the Q-value changes for the run's summary.
+    pub fn never() -> Self {
+        Self::new(Opponent::Random)
+    }
+
+    /// Called after every episode with the Q-value changes it made; returns the reason to stop,
+    /// if there is one.
+    pub fn check(&mut self, agent: &QLearningAgent, changes: &ChangeWindow, episodes_played: usize) -> Option<StopReason> {
+        self.changes.merge(changes);
+        if episodes_played.is_multiple_of(self.window) {
+            self.last_window = std::mem::take(&mut self.changes);
+            if let Some(max_change) = self.max_change
+                && self.last_window.max < max_change
+                && self.last_window.mean() < self.mean_change
+            {
+                return Some(StopReason::Converged);
+            }
+        }
+        if let Some(interval) = self.evaluation_interval
+            && episodes_played.is_multiple_of(interval)
+        {
+            let score = evaluate(agent, &mut self.opponent, self.evaluation_games).score();
+            self.last_score = Some(score);
+            if score > self.best_score + self.min_improvement {
+                self.best_score = score;
+                self.stale_evaluations = 0;
+            } else {
+                self.stale_evaluations += 1;
+                if self.stale_evaluations >= self.patience {
+                    return Some(StopReason::Plateau);
+                }
+            }
+        }
+        None
+    }
+
+    /// Summarises the last complete window, or the episodes so far in a run shorter than one.
+    pub fn summary(&self, episodes: usize, stop_reason: StopReason) -> TrainingSummary {
+        let window = if self.last_window.updates > 0 { &self.last_window } else { &self.changes };
+        TrainingSummary {
+            episodes,
+            stop_reason,
+            max_change: window.max,
+            mean_change: window.mean(),
+            evaluation: self.last_score,
+            exploration: None,
+        }
+    }
+}
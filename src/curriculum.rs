@@ -0,0 +1,229 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+use crate::opponent::move_scores;
+use crate::{random, Board, Cell, QLearningAgent};
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+const MIN_WEIGHT: f64 = 0.1;
+const MAX_WEIGHT: f64 = 16.0;
+
+/// Mid-game starting positions for training episodes.
+///
+/// Each episode starts from a sampled position with a probability that is annealed linearly
+/// from `start_rate` to `end_rate` over the run. Every `review_interval` episodes the positions
+/// are re-scored: the ones the agent still gets wrong gain weight, the ones it has learned lose
+/// it. An automatic curriculum also adds every reachable position the agent currently blunders in.
+#[derive(Debug)]
+pub struct Curriculum {
+    positions: Vec<String>,
+    weights: Vec<f64>,
+    auto: bool,
+    pub start_rate: f64,
+    pub end_rate: f64,
+    pub review_interval: usize,
+    cache: HashMap<String, i32>,
+}
+
+impl Curriculum {
+    fn new(positions: Vec<String>, auto: bool) -> Self {
+        let weights = vec![1.0; positions.len()];
+        Curriculum {
+            positions,
+            weights,
+            auto,
+            start_rate: 0.5,
+            end_rate: 0.2,
+            review_interval: 5000,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Loads one `board_state` string per line. Blank lines and lines starting with `#` are skipped.
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let contents = fs::read_to_string(path)?;
+        let mut positions = Vec::new();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match Board::from_state(line) {
+                Some(board) if !board.is_game_over().0 => positions.push(line.to_string()),
+                _ => return Err(format!("{}:{}: '{}' is not a playable position", path, number + 1, line).into()),
+            }
+        }
+        if positions.is_empty() {
+            return Err(format!("{} contains no positions", path).into());
+        }
+        Ok(Self::new(positions, false))
+    }
+
+    /// A curriculum that is filled from the agent's own blunders at every review.
+    pub fn auto() -> Self {
+        Self::new(Vec::new(), true)
+    }
+
+    /// Returns a starting position for the episode, or `None` to start from an empty board.
+    pub fn start_position(&self, episode: usize, episodes: usize) -> Option<Board> {
+        let progress = episode as f64 / episodes.max(1) as f64;
+        let rate = self.start_rate + (self.end_rate - self.start_rate) * progress;
+        let mut rng = random::rng();
+        if self.positions.is_empty() || rng.random::<f64>() >= rate {
+            return None;
+        }
+        let total: f64 = self.weights.iter().sum();
+        let mut target = rng.random::<f64>() * total;
+        for (position, weight) in self.positions.iter().zip(&self.weights) {
+            if target < *weight {
+                return Board::from_state(position);
+            }
+            target -= weight;
+        }
+        Board::from_state(self.positions.last().unwrap())
+    }
+
+    /// Re-weights the positions according to whether `agent` still blunders in them.
+    pub fn review(&mut self, agent: &QLearningAgent) {
+        for (position, weight) in self.positions.iter().zip(self.weights.iter_mut()) {
+            *weight = if blunders(agent, position, &mut self.cache) {
+                (*weight * 2.0).min(MAX_WEIGHT)
+            } else {
+                (*weight * 0.5).max(MIN_WEIGHT)
+            };
+        }
+        if self.auto {
+            let known: HashSet<String> = self.positions.iter().cloned().collect();
+            for position in reachable_positions() {
+                if !known.contains(&position) && blunders(agent, &position, &mut self.cache) {
+                    self.positions.push(position);
+                    self.weights.push(1.0);
+                }
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+}
+
+/// Whether the agent's greedy move in `state` is worse than the minimax optimum for either
+/// player that could be on move.
+fn blunders(agent: &QLearningAgent, state: &str, cache: &mut HashMap<String, i32>) -> bool {
+    let Some(mut board) = Board::from_state(state) else {
+        return false;
+    };
+    let x_count = state.chars().filter(|&c| c == 'X').count();
+    let o_count = state.chars().filter(|&c| c == 'O').count();
+    let players = if x_count == o_count { vec![Cell::X, Cell::O] } else { vec![board.get_current_player().marker] };
+    players.into_iter().any(|player| {
+        board.set_current_player(player);
+        let scores = move_scores(&board, cache);
+        let best_score = scores.iter().map(|&(_, score)| score).max().unwrap();
+        match agent.greedy_action(state, &board.available_moves()) {
+            Some(action) => scores.iter().any(|&(pos, score)| pos == action && score < best_score),
+            // an unvisited state is played randomly, which only matters if some move is worse
+            None => scores.iter().any(|&(_, score)| score < best_score),
+        }
+    })
+}
+
+/// Every non-terminal position reachable from the empty board, with either player starting.
+pub fn reachable_positions() -> Vec<String> {
+    fn visit(board: &Board, seen: &mut HashSet<String>, positions: &mut HashSet<String>) {
+        let state = board.board_state();
+        if board.is_game_over().0 || !seen.insert(format!("{}{}", state, board.get_current_player().marker)) {
+            return;
+        }
+        positions.insert(state);
+        for pos in board.available_moves() {
+            let mut child = board.clone();
+            child.make_move(pos.0, pos.1);
+            visit(&child, seen, positions);
+        }
+    }
+    let mut seen = HashSet::new();
+    let mut positions = HashSet::new();
+    for player in [Cell::X, Cell::O] {
+        let mut board = Board::new();
+        board.set_current_player(player);
+        visit(&board, &mut seen, &mut positions);
+    }
+    positions.remove(&Board::new().board_state());
+    let mut positions: Vec<String> = positions.into_iter().collect();
+    positions.sort();
+    positions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every position reachable with X starting, finished or not.
+    fn count_positions(board: &Board, seen: &mut HashSet<String>, finished: &mut usize) {
+        if !seen.insert(board.board_state()) {
+            return;
+        }
+        if board.is_game_over().0 {
+            *finished += 1;
+            return;
+        }
+        for (row, col) in board.available_moves() {
+            let mut child = board.clone();
+            child.make_move(row, col);
+            count_positions(&child, seen, finished);
+        }
+    }
+
+    #[test]
+    fn reachable_positions_match_the_known_counts() {
+        let mut board = Board::new();
+        board.set_current_player(Cell::X);
+        let (mut seen, mut finished) = (HashSet::new(), 0);
+        count_positions(&board, &mut seen, &mut finished);
+        assert_eq!(seen.len(), 5478);
+        assert_eq!(finished, 958);
+
+        let positions = reachable_positions();
+        let x_first: Vec<&String> = positions.iter().filter(|p| p.matches('X').count() >= p.matches('O').count()).collect();
+        // the unfinished ones, less the empty board
+        assert_eq!(x_first.len(), 5478 - 958 - 1);
+        assert!(x_first.iter().all(|p| seen.contains(*p)));
+        // with O starting the same positions arise with the markers swapped
+        let ahead = |marker: char, other: char| positions.iter().filter(|p| p.matches(marker).count() > p.matches(other).count()).count();
+        assert_eq!(ahead('X', 'O'), ahead('O', 'X'));
+    }
+
+    #[test]
+    fn start_position_follows_the_rate_and_weights() {
+        random::seed(27);
+        let mut curriculum = Curriculum::new(vec!["X---O----".to_string(), "XO--X----".to_string()], false);
+        curriculum.start_rate = 0.0;
+        curriculum.end_rate = 0.0;
+        assert!((0..100).all(|episode| curriculum.start_position(episode, 100).is_none()));
+
+        curriculum.start_rate = 1.0;
+        curriculum.end_rate = 1.0;
+        curriculum.weights = vec![MIN_WEIGHT, MAX_WEIGHT];
+        let starts: Vec<String> = (0..1000).map(|episode| curriculum.start_position(episode, 1000).unwrap().board_state()).collect();
+        let heavy = starts.iter().filter(|state| *state == "XO--X----").count();
+        assert!(starts.iter().all(|state| curriculum.positions.contains(state)));
+        // 16 / 16.1 of the starts are expected on the heavier position
+        assert!(heavy > 980, "{} of 1000 starts on the heavier position", heavy);
+
+        // annealed from always to never over the run
+        curriculum.end_rate = 0.0;
+        assert!(curriculum.start_position(0, 1000).is_some());
+        assert!(curriculum.start_position(1000, 1000).is_none());
+    }
+}
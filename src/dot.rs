@@ -0,0 +1,117 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! Graphviz export of the game tree below a position, annotated with what the agent learned.
+
+use crate::opponent::move_scores;
+use crate::{Board, Cell, QLearningAgent};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+
+const GREEDY_COLOR: &str = "red";
+const OPTIMAL_COLOR: &str = "blue";
+
+/// Renders the positions reachable from `root` in at most `depth` moves as a DOT graph.
+///
+/// Positions reached by several move orders are drawn once. Each edge is labelled with the
+/// move and the agent's Q-value for it; the agent's greedy line from `root` is drawn in red,
+/// and with `optimal` every move minimax rates best is drawn in blue.
+pub fn game_tree(agent: &QLearningAgent, root: &Board, depth: usize, optimal: bool) -> String {
+    let greedy_line = greedy_line(agent, root, depth);
+    let greedy_positions: HashSet<&str> = greedy_line.iter().flat_map(|(from, to)| [from.as_str(), to.as_str()]).collect();
+    let mut cache = HashMap::new();
+    let mut dot = String::from("digraph game_tree {\n");
+    dot.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+    dot.push_str("    edge [fontname=\"monospace\", fontsize=10];\n");
+
+    let mut seen = HashSet::from([root.board_state()]);
+    let mut queue = VecDeque::from([(root.clone(), 0)]);
+    while let Some((game, level)) = queue.pop_front() {
+        let state = game.board_state();
+        let (over, winner) = game.is_game_over();
+        let mut attributes = String::new();
+        if over {
+            attributes.push_str(", peripheries=2");
+        }
+        if greedy_positions.contains(state.as_str()) {
+            let _ = write!(attributes, ", color={}, penwidth=2", GREEDY_COLOR);
+        }
+        let _ = writeln!(dot, "    \"{}\" [label=\"{}\"{}];", state, node_label(&game, over, winner), attributes);
+        if over || level >= depth {
+            continue;
+        }
+
+        let best = if optimal {
+            let scores = move_scores(&game, &mut cache);
+            let top = scores.iter().map(|&(_, score)| score).max();
+            scores.into_iter().filter(|&(_, score)| Some(score) == top).map(|(pos, _)| pos).collect()
+        } else {
+            Vec::new()
+        };
+        for (row, col) in game.available_moves() {
+            let mut child = game.clone();
+            child.make_move(row, col);
+            let child_state = child.board_state();
+            let action = format!("{},{}", row, col);
+            let value = agent
+                .q_table
+                .get(&state)
+                .and_then(|actions| actions.get(&action))
+                .map_or_else(|| "?".to_string(), |entry| format!("{:.3}", entry.value));
+            let greedy = greedy_line.contains(&(state.clone(), child_state.clone()));
+            let colors: Vec<&str> = [(greedy, GREEDY_COLOR), (best.contains(&(row, col)), OPTIMAL_COLOR)]
+                .into_iter()
+                .filter_map(|(on, color)| on.then_some(color))
+                .collect();
+            let style = if colors.is_empty() {
+                String::new()
+            } else {
+                format!(", color=\"{}\", penwidth=2", colors.join(":"))
+            };
+            let _ = writeln!(dot, "    \"{}\" -> \"{}\" [label=\"{} Q={}\"{}];", state, child_state, action, value, style);
+            if seen.insert(child_state) {
+                queue.push_back((child, level + 1));
+            }
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// The positions the agent moves between when it plays both sides greedily from `root`.
+fn greedy_line(agent: &QLearningAgent, root: &Board, depth: usize) -> Vec<(String, String)> {
+    let mut line = Vec::new();
+    let mut game = root.clone();
+    while line.len() < depth && !game.is_game_over().0 {
+        let state = game.board_state();
+        let Some((row, col)) = agent.greedy_action(&state, &game.available_moves()) else { break };
+        game.make_move(row, col);
+        line.push((state, game.board_state()));
+    }
+    line
+}
+
+/// The grid as a line of text per row, with the side to move or the result underneath.
+fn node_label(game: &Board, over: bool, winner: Option<Cell>) -> String {
+    let mut label = String::new();
+    for row in game.rows() {
+        for cell in row {
+            let _ = write!(label, "{} ", cell);
+        }
+        label.pop();
+        label.push_str("\\n");
+    }
+    match (over, winner) {
+        (_, Some(winner)) => {
+            let _ = write!(label, "{} wins", winner);
+        }
+        (true, None) => label.push_str("draw"),
+        (false, _) => {
+            let _ = write!(label, "{} to move", game.get_current_player().marker);
+        }
+    }
+    label
+}
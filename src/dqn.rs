@@ -0,0 +1,368 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! Deep Q-learning: an `Mlp` in place of the Q-table, so that values generalise between
+//! positions instead of being stored for each one.
+//!
+//! The network sees every cell one-hot as empty, the mover's or the opponent's, so that what it
+//! learns for one side carries over to the other, followed by the side to move one-hot,
+//! and outputs a value for playing on each cell from the mover's point of view; occupied cells
+//! are never chosen. Training plays episodes like those of `train_q_learning`, against itself
+//! or the opponent pool, but learns from minibatches drawn from a replay memory towards targets
+//! computed by a target network, a copy of the network refreshed every `target_interval`
+//! gradient steps.
+//!
+//! Only the rewards for winning, losing and drawing are shared with the tabular agent. Its
+//! shaping is left out: blocks are not forced while training nor rewarded, and a win is not
+//! propagated back through the winner's earlier moves, since the network's targets already
+//! carry the result back through the replayed transitions. Neither are curricula, prioritised
+//! replay, early stopping, metrics, checkpoints, resuming or rule variants supported;
+//! `train_dqn` refuses options that ask for them.
+
+use crate::game::Agent;
+use crate::metrics::EpisodeStats;
+use crate::mlp::{Adam, Mlp};
+use crate::model::{self, TrainingMetadata};
+use crate::opponent::Opponent;
+use crate::rules::Rules;
+use crate::{random, Board, BoardSize, Cell, TrainingOptions, DRAW_REWARD, LOSS_REWARD, MIN_EPSILON, WIN_REWARD};
+use rand::Rng;
+use rand::prelude::IndexedRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Where `train-network` saves by default; the GUI's `FILENAME` holds a tabular agent.
+pub const NETWORK_FILENAME: &str = "network.json";
+pub const DEFAULT_EPISODES: usize = 20000;
+pub const DEFAULT_HIDDEN: [usize; 2] = [128, 128];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DqnAgent {
+    pub network: Mlp,
+    pub learning_rate: f64,
+    pub gamma: f64,
+    pub epsilon: f64,
+    pub batch_size: usize,
+    pub replay_capacity: usize,
+    /// Gradient steps between refreshes of the target network.
+    pub target_interval: usize,
+    /// Whether the agent is being trained; this is never saved, so loaded agents play greedily.
+    #[serde(skip)]
+    pub train: bool,
+    /// Saved in the model header rather than with the network.
+    #[serde(skip)]
+    pub metadata: TrainingMetadata,
+}
+
+impl DqnAgent {
+    /// A fresh agent for boards of `board` with hidden layers of the given sizes.
+    pub fn new(board: BoardSize, hidden: &[usize]) -> Self {
+        let sizes: Vec<usize> = std::iter::once(input_size(board)).chain(hidden.iter().copied()).chain([board.cells()]).collect();
+        DqnAgent {
+            network: Mlp::new(&sizes),
+            learning_rate: 0.001,
+            gamma: 0.9,
+            epsilon: 0.9,
+            batch_size: 32,
+            replay_capacity: 200000,
+            target_interval: 500,
+            train: true,
+            metadata: TrainingMetadata { board, ..Default::default() },
+        }
+    }
+
+    /// The network's input for the position and player to move of `game`.
+    pub fn encode(game: &Board) -> Vec<f64> {
+        let size = game.size();
+        let mover = game.get_current_player().marker;
+        let mut input = vec![0.0; input_size(size)];
+        for i in 0..size.cells() {
+            let channel = match game.cell(i / size.width, i % size.width) {
+                Cell::Empty => 0,
+                marker if marker == mover => 1,
+                _ => 2,
+            };
+            input[3 * i + channel] = 1.0;
+        }
+        let side = if mover == Cell::X { 0 } else { 1 };
+        input[3 * size.cells() + side] = 1.0;
+        input
+    }
+
+    /// The highest valued empty cell; the game must not be over.
+    pub fn greedy_action(&self, game: &Board) -> (usize, usize) {
+        let input = Self::encode(game);
+        let index = best_action(&self.network.forward(&input), &input).unwrap().0;
+        (index / game.size().width, index % game.size().width)
+    }
+
+    /// Epsilon-greedy while training, greedy otherwise. Also returns whether it explored.
+    fn choose_action(&self, game: &Board) -> ((usize, usize), bool) {
+        let mut rng = random::rng();
+        if self.train && rng.random::<f64>() < self.epsilon {
+            (*game.available_moves().choose(&mut rng).unwrap(), true)
+        } else {
+            (self.greedy_action(game), false)
+        }
+    }
+
+    pub fn save_to_path(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        model::save_network(self, path)
+    }
+
+    pub fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        model::load_network(path)
+    }
+}
+
+impl Agent<Board> for DqnAgent {
+    fn choose_move(&mut self, game: &Board) -> (usize, usize) {
+        self.choose_action(game).0
+    }
+}
+
+/// The length of the encoding of positions on `board`.
+pub fn input_size(board: BoardSize) -> usize {
+    3 * board.cells() + 2
+}
+
+/// The index and value of the highest of `values` among the cells `input` shows empty.
+fn best_action(values: &[f64], input: &[f64]) -> Option<(usize, f64)> {
+    values
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| input[3 * i] == 1.0)
+        .map(|(i, &value)| (i, value))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// Positions are kept as boards, far smaller than their encodings, and encoded when sampled.
+struct Transition {
+    state: Board,
+    action: usize,
+    reward: f64,
+    /// The position the mover faces next, `None` once the game is over.
+    next: Option<Board>,
+    /// In self-play the next position is the opponent's, whose gain is the mover's loss.
+    next_is_opponents: bool,
+}
+
+/// The replay memory, target network and optimiser of a training run.
+struct Learner {
+    memory: VecDeque<Transition>,
+    target: Mlp,
+    optimizer: Adam,
+    steps: usize,
+}
+
+impl Learner {
+    fn new(agent: &DqnAgent) -> Self {
+        Learner {
+            memory: VecDeque::with_capacity(agent.replay_capacity),
+            target: agent.network.clone(),
+            optimizer: Adam::new(&agent.network, agent.learning_rate),
+            steps: 0,
+        }
+    }
+
+    /// Remembers a transition and takes a gradient step on a minibatch drawn from memory.
+    fn learn(&mut self, agent: &mut DqnAgent, transition: Transition) {
+        if self.memory.len() == agent.replay_capacity {
+            self.memory.pop_front();
+        }
+        self.memory.push_back(transition);
+        if self.memory.len() < agent.batch_size {
+            return;
+        }
+        let mut rng = random::rng();
+        let mut gradients = agent.network.zero_gradients();
+        for _ in 0..agent.batch_size {
+            let transition = &self.memory[rng.random_range(0..self.memory.len())];
+            let future = transition.next.as_ref().map_or(0.0, |next| {
+                let next = DqnAgent::encode(next);
+                let value = best_action(&self.target.forward(&next), &next).map_or(0.0, |(_, value)| value);
+                if transition.next_is_opponents { -value } else { value }
+            });
+            let target = transition.reward + agent.gamma * future;
+            agent.network.accumulate(&DqnAgent::encode(&transition.state), transition.action, target, &mut gradients);
+        }
+        self.optimizer.step(&mut agent.network, &gradients, agent.batch_size);
+        self.steps += 1;
+        if self.steps.is_multiple_of(agent.target_interval) {
+            self.target = agent.network.clone();
+        }
+    }
+}
+
+fn action_index(game: &Board, (row, col): (usize, usize)) -> usize {
+    row * game.size().width + col
+}
+
+/// Plays one episode with the agent on both sides.
+fn self_play_episode(agent: &mut DqnAgent, learner: &mut Learner, mut game: Board) -> EpisodeStats {
+    let mut stats = EpisodeStats::default();
+    while !game.is_game_over().0 {
+        let marker = game.get_current_player().marker;
+        let state = game.clone();
+        let (action, explore) = agent.choose_action(&game);
+        stats.record_move(explore, false);
+        game.make_move(action.0, action.1);
+        let (game_over, winner) = game.is_game_over();
+        let reward = match winner {
+            Some(winner) if winner == marker => WIN_REWARD,
+            _ if game_over => DRAW_REWARD,
+            _ => 0.0,
+        };
+        let next = (!game_over).then(|| game.clone());
+        let transition = Transition { state, action: action_index(&game, action), reward, next, next_is_opponents: true };
+        learner.learn(agent, transition);
+        stats.winner = winner;
+    }
+    stats
+}
+
+/// Plays one episode with the agent on a random side against `opponent`. Each of the agent's
+/// moves is learned from once the opponent has replied.
+fn opponent_episode(agent: &mut DqnAgent, learner: &mut Learner, opponent: &mut Opponent, mut game: Board) -> EpisodeStats {
+    let agent_marker = if random::rng().random_bool(0.5) { Cell::X } else { Cell::O };
+    let mut stats = EpisodeStats { agent_side: Some(agent_marker), ..Default::default() };
+    // the agent's last position and action, waiting for the opponent's reply
+    let mut pending: Option<(Board, usize)> = None;
+    loop {
+        let (game_over, winner) = game.is_game_over();
+        if game_over {
+            if let Some((state, action)) = pending.take() {
+                let reward = match winner {
+                    Some(marker) if marker == agent_marker => WIN_REWARD,
+                    Some(_) => LOSS_REWARD,
+                    None => DRAW_REWARD,
+                };
+                learner.learn(agent, Transition { state, action, reward, next: None, next_is_opponents: false });
+            }
+            stats.winner = winner;
+            break;
+        }
+        if game.get_current_player().marker != agent_marker {
+            let action = opponent.choose_move(&game);
+            game.make_move(action.0, action.1);
+            stats.moves += 1;
+            continue;
+        }
+        if let Some((state, action)) = pending.take() {
+            let next = Some(game.clone());
+            learner.learn(agent, Transition { state, action, reward: 0.0, next, next_is_opponents: false });
+        }
+        let state = game.clone();
+        let (action, explore) = agent.choose_action(&game);
+        stats.record_move(explore, false);
+        game.make_move(action.0, action.1);
+        pending = Some((state, action_index(&game, action)));
+    }
+    stats
+}
+
+/// Fails on the options only tabular agents support, and on opponents that cannot play on
+/// `options.board` or that need a Q-table.
+fn check_options(options: &TrainingOptions) -> Result<(), String> {
+    let unsupported = [
+        ("curricula", options.curriculum.is_some()),
+        ("prioritised replay", options.replay.is_some()),
+        ("early stopping", options.early_stopping.is_some()),
+        ("metrics", options.metrics.is_some()),
+        ("checkpoints", options.checkpoints.is_some()),
+        ("resuming", options.resume),
+        ("rule variants", options.rules != Rules::Standard),
+    ];
+    if let Some((name, _)) = unsupported.iter().find(|&&(_, requested)| requested) {
+        return Err(format!("{} is not supported for network agents", name));
+    }
+    if options.opponents.has_past_self() {
+        return Err("past snapshots are only taken of tabular agents".to_string());
+    }
+    options.opponents.check_board(options.board)
+}
+
+/// Trains `agent` for `options.episodes` episodes on `options.board` against `options.opponents`,
+/// then saves it to `options.output`. Epsilon decays linearly to `MIN_EPSILON` over the run, and
+/// the learning rate to a tenth of `agent.learning_rate`. Any other option is refused.
+pub fn train_dqn(agent: &mut DqnAgent, options: &mut TrainingOptions) -> Result<(), String> {
+    check_options(options)?;
+    let seed = options.seed.unwrap_or_else(rand::random);
+    random::seed(seed);
+    agent.metadata.seed = Some(seed);
+    agent.metadata.board = options.board;
+    let epsilon_start = agent.epsilon;
+    agent.metadata.initial_epsilon = Some(epsilon_start);
+    agent.train = true;
+    let mut learner = Learner::new(agent);
+    let (mut exploration, mut exploitation) = (0, 0);
+    for episode in 0..options.episodes {
+        let game = Board::with_size(options.board);
+        let stats = match options.opponents.sample() {
+            Opponent::SelfPlay => self_play_episode(agent, &mut learner, game),
+            opponent => opponent_episode(agent, &mut learner, opponent, game),
+        };
+        exploration += stats.explored;
+        exploitation += stats.exploited;
+        let progress = episode as f64 / options.episodes as f64;
+        agent.epsilon = epsilon_start - progress * (epsilon_start - MIN_EPSILON).max(0.0);
+        // smaller steps towards the end settle the network instead of leaving it mid-jitter
+        learner.optimizer.learning_rate = agent.learning_rate * (1.0 - 0.9 * progress);
+    }
+    agent.train = false;
+    agent.metadata.episodes += options.episodes;
+    if let Some(output) = &options.output {
+        match agent.save_to_path(output) {
+            Ok(()) => println!("Saved network to {}", output),
+            Err(err) => eprintln!("Failed to save network: {}", err),
+        }
+    }
+    let total_loop = exploration + exploitation;
+    println!("Exploration: {:.2}, Exploitation: {:.2}", (exploration as f64)/(total_loop as f64), (exploitation as f64)/(total_loop as f64));
+    println!("Gradient steps: {}", learner.steps);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convergence::EarlyStopping;
+    use crate::opponent::OpponentPool;
+
+    #[test]
+    fn tabular_only_options_are_refused() {
+        let options = || TrainingOptions { output: None, ..TrainingOptions::new(10) };
+        let refused = [
+            TrainingOptions { early_stopping: Some(EarlyStopping::never()), ..options() },
+            TrainingOptions { resume: true, ..options() },
+            TrainingOptions { rules: Rules::Misere, ..options() },
+            TrainingOptions { opponents: OpponentPool::parse("past@100").unwrap(), ..options() },
+            TrainingOptions { board: BoardSize::parse("4x4:3").unwrap(), opponents: OpponentPool::parse("minimax").unwrap(), ..options() },
+        ];
+        for mut options in refused {
+            let mut agent = DqnAgent::new(options.board, &[8]);
+            assert!(train_dqn(&mut agent, &mut options).is_err());
+            assert_eq!(agent.metadata.episodes, 0);
+        }
+        let mut agent = DqnAgent::new(BoardSize::CLASSIC, &[8]);
+        let mut resumed = TrainingOptions { resume: true, ..options() };
+        assert_eq!(train_dqn(&mut agent, &mut resumed), Err("resuming is not supported for network agents".to_string()));
+        assert_eq!(train_dqn(&mut agent, &mut options()), Ok(()));
+        assert_eq!(agent.metadata.episodes, 10);
+    }
+
+    /// Trains with the defaults of `train-network`, which takes minutes even in a release
+    /// build; run it with `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore]
+    fn self_play_learns_perfect_play_on_3x3() {
+        let mut agent = DqnAgent::new(BoardSize::CLASSIC, &DEFAULT_HIDDEN);
+        let mut options = TrainingOptions { seed: Some(50), output: None, ..TrainingOptions::new(DEFAULT_EPISODES) };
+        train_dqn(&mut agent, &mut options).unwrap();
+        let audit = crate::opponent::audit(&mut agent);
+        assert_eq!(audit.mistakes, 0, "{} mistakes in {} positions", audit.mistakes, audit.positions);
+    }
+}
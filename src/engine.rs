@@ -0,0 +1,154 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! A line-based protocol for driving an engine from another program.
+//!
+//! Every command gets exactly one line in reply, so a harness can write a command and read a
+//! line back:
+//!
+//! The engine plays on one board size throughout, the classic 3×3 one unless it was started
+//! with another.
+//!
+//! - `newgame`: starts from the empty board with X to move. Replies `ok`.
+//! - `position <board_state> [x|o]`: sets up a position. The side to move follows from the
+//!   marker counts; when they are equal it is the given side, X by default. Replies `ok`.
+//! - `play <row>,<col>`: makes a move for the side to move. Replies `ok`.
+//! - `go`: replies `bestmove <row>,<col>` for the side to move, without making the move.
+//! - `state`: replies `state <board_state> <side to move>`, or the result once the game is over.
+//! - `setoption <name> <value>`: sets `alpha`, `gamma`, `epsilon` or `explore` (`true` or
+//!   `false`) of an agent, `mistake` of a minimax engine, or the random `seed`. Replies `ok`.
+//! - `isready`: replies `readyok`.
+//! - `quit`: exits without a reply.
+//!
+//! Anything that fails is answered with `error <reason>`.
+
+use crate::opponent::Opponent;
+use crate::{random, Board, BoardSize, Cell};
+use std::io::{self, BufRead, Write};
+
+pub fn run(engine: &mut Opponent, size: BoardSize, input: &mut impl BufRead, output: &mut impl Write) -> io::Result<()> {
+    let mut board = new_game(size);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let reply = match words.as_slice() {
+            [] => continue,
+            ["quit"] => return Ok(()),
+            ["isready"] => Ok("readyok".to_string()),
+            ["newgame"] => {
+                board = new_game(size);
+                Ok("ok".to_string())
+            }
+            ["position", state, side @ ..] => position(size, state, side).map(|position| {
+                board = position;
+                "ok".to_string()
+            }),
+            ["play", action] => play(&mut board, action).map(|()| "ok".to_string()),
+            ["go"] => go(engine, &board),
+            ["state"] => Ok(state(&board)),
+            ["setoption", name, value] => set_option(engine, name, value).map(|()| "ok".to_string()),
+            _ => Err(format!("unknown command '{}'", line.trim())),
+        };
+        match reply {
+            Ok(reply) => writeln!(output, "{}", reply)?,
+            Err(err) => writeln!(output, "error {}", err)?,
+        }
+        output.flush()?;
+    }
+}
+
+fn new_game(size: BoardSize) -> Board {
+    let mut board = Board::with_size(size);
+    board.set_current_player(Cell::X);
+    board
+}
+
+fn position(size: BoardSize, state: &str, side: &[&str]) -> Result<Board, String> {
+    let mut board = Board::from_state_with_size(size, state).ok_or_else(|| format!("invalid board state '{}'", state))?;
+    if state.matches('X').count() == state.matches('O').count() {
+        board.set_current_player(match side {
+            [] | ["x" | "X"] => Cell::X,
+            ["o" | "O"] => Cell::O,
+            _ => return Err(format!("unknown side '{}'", side.join(" "))),
+        });
+    }
+    Ok(board)
+}
+
+fn play(board: &mut Board, action: &str) -> Result<(), String> {
+    if board.is_game_over().0 {
+        return Err("game over".to_string());
+    }
+    let (row, col) = action
+        .split_once(',')
+        .and_then(|(row, col)| Some((row.parse::<usize>().ok()?, col.parse::<usize>().ok()?)))
+        .filter(|&(row, col)| row < board.size().height && col < board.size().width)
+        .ok_or_else(|| format!("invalid move '{}'", action))?;
+    if !board.make_move(row, col).0.move_successful {
+        return Err(format!("cell {},{} is taken", row, col));
+    }
+    Ok(())
+}
+
+fn go(engine: &mut Opponent, board: &Board) -> Result<String, String> {
+    if board.is_game_over().0 {
+        return Err("game over".to_string());
+    }
+    let (row, col) = engine.choose_move(board);
+    Ok(format!("bestmove {},{}", row, col))
+}
+
+fn state(board: &Board) -> String {
+    let status = match board.is_game_over() {
+        (_, Some(winner)) => format!("{}-wins", winner),
+        (true, None) => "draw".to_string(),
+        (false, _) => board.get_current_player().marker.to_string(),
+    };
+    format!("state {} {}", board.board_state(), status)
+}
+
+fn set_option(engine: &mut Opponent, name: &str, value: &str) -> Result<(), String> {
+    let number = || value.parse::<f64>().map_err(|_| format!("invalid value '{}' for {}", value, name));
+    match (name, engine) {
+        ("seed", _) => random::seed(value.parse().map_err(|_| format!("invalid seed '{}'", value))?),
+        ("alpha", Opponent::Snapshot(agent)) => agent.alpha = number()?,
+        ("gamma", Opponent::Snapshot(agent)) => agent.gamma = number()?,
+        ("epsilon", Opponent::Snapshot(agent)) => agent.epsilon = number()?,
+        ("explore", Opponent::Snapshot(agent)) => {
+            agent.train = value.parse().map_err(|_| format!("invalid value '{}' for explore", value))?
+        }
+        ("mistake", Opponent::Minimax { mistake_probability, .. }) => {
+            let probability = number()?;
+            if !(0.0..=1.0).contains(&probability) {
+                return Err(format!("mistake probability must be within [0, 1], got {}", probability));
+            }
+            *mistake_probability = probability;
+        }
+        (name, _) => return Err(format!("option '{}' is not supported by this engine", name)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plays_on_the_board_it_was_started_with() {
+        let size = BoardSize::parse("4x4:3").unwrap();
+        let mut input = "play 3,3\nplay 3,4\nposition XX-----------OO-\ngo\nplay 0,2\nstate\n".as_bytes();
+        let mut output = Vec::new();
+        run(&mut Opponent::Random, size, &mut input, &mut output).unwrap();
+        let replies: Vec<&str> = std::str::from_utf8(&output).unwrap().lines().collect();
+        assert_eq!(replies[..3], ["ok", "error invalid move '3,4'", "ok"]);
+        assert!(replies[3].starts_with("bestmove "));
+        assert_eq!(replies[4..], ["ok", "state XXX----------OO- X-wins"]);
+    }
+}
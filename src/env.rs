@@ -0,0 +1,165 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! A reinforcement-learning environment in the style of Gym, for learners other than
+//! `QLearningAgent`.
+//!
+//! Actions are cell indices, `row * width + col`. The learner plays one side and a built-in
+//! `Opponent` answers each of its moves automatically, so every step hands control back to
+//! the learner or ends the game.
+
+use crate::opponent::Opponent;
+use crate::{random, Board, BoardSize, Cell};
+use rand::Rng;
+
+/// The board from the learner's point of view.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    /// 1 for the learner's marks, -1 for the opponent's and 0 for empty cells, row by row.
+    pub cells: Vec<i8>,
+    /// Which actions are legal.
+    pub mask: Vec<bool>,
+    pub board_state: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Info {
+    pub learner: Cell,
+    pub winner: Option<Cell>,
+    /// Set when the step ended the game because the learner chose an occupied cell.
+    pub illegal_action: bool,
+    /// The cell the opponent answered with, if it moved.
+    pub opponent_action: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rewards {
+    pub win: f64,
+    pub draw: f64,
+    pub loss: f64,
+    pub illegal: f64,
+    /// For every move that does not end the game.
+    pub step: f64,
+}
+
+impl Default for Rewards {
+    fn default() -> Self {
+        Rewards { win: 1.0, draw: 0.0, loss: -1.0, illegal: -1.0, step: 0.0 }
+    }
+}
+
+pub struct TicTacToeEnv {
+    board: Board,
+    pub opponent: Opponent,
+    /// The side the learner plays, or `None` to draw it at every reset.
+    pub learner_side: Option<Cell>,
+    pub rewards: Rewards,
+    learner: Cell,
+    done: bool,
+}
+
+impl TicTacToeEnv {
+    /// An environment on the classic 3×3 board.
+    pub fn new(opponent: Opponent) -> Result<Self, String> {
+        Self::with_size(opponent, BoardSize::CLASSIC)
+    }
+
+    /// Fails if the opponent cannot play on boards of `size`.
+    pub fn with_size(opponent: Opponent, size: BoardSize) -> Result<Self, String> {
+        opponent.check_board(size)?;
+        Ok(TicTacToeEnv {
+            board: Board::with_size(size),
+            opponent,
+            learner_side: None,
+            rewards: Rewards::default(),
+            learner: Cell::X,
+            done: true,
+        })
+    }
+
+    /// Starts a new game, seeding the random number generator first if `seed` is given. If
+    /// the opponent starts, its first move has already been made.
+    pub fn reset(&mut self, seed: Option<u64>) -> Observation {
+        if let Some(seed) = seed {
+            random::seed(seed);
+        }
+        self.board = Board::with_size(self.board.size());
+        self.learner = self.learner_side.unwrap_or_else(|| if random::rng().random_bool(0.5) { Cell::X } else { Cell::O });
+        self.done = false;
+        if self.board.get_current_player().marker != self.learner {
+            let (row, col) = self.opponent.choose_move(&self.board);
+            self.board.make_move(row, col);
+        }
+        self.observation()
+    }
+
+    /// Plays the learner's `action` and the opponent's reply. Stepping a finished game, or
+    /// with an action outside the board, is an error.
+    pub fn step(&mut self, action: usize) -> Result<(Observation, f64, bool, Info), String> {
+        if self.done {
+            return Err("the game is over; call reset".to_string());
+        }
+        let width = self.board.size().width;
+        if action >= self.board.size().cells() {
+            return Err(format!("action {} is outside the board", action));
+        }
+        let mut info = Info { learner: self.learner, winner: None, illegal_action: false, opponent_action: None };
+        let (status, winner) = self.board.make_move(action / width, action % width);
+        if !status.move_successful {
+            self.done = true;
+            info.illegal_action = true;
+            return Ok((self.observation(), self.rewards.illegal, true, info));
+        }
+        if !status.game_over {
+            let (row, col) = self.opponent.choose_move(&self.board);
+            self.board.make_move(row, col);
+            info.opponent_action = Some(row * width + col);
+        }
+        let (done, winner) = match winner {
+            Some(winner) => (true, Some(winner)),
+            None => self.board.is_game_over(),
+        };
+        self.done = done;
+        info.winner = winner;
+        let reward = match (done, winner) {
+            (false, _) => self.rewards.step,
+            (true, Some(winner)) if winner == self.learner => self.rewards.win,
+            (true, Some(_)) => self.rewards.loss,
+            (true, None) => self.rewards.draw,
+        };
+        Ok((self.observation(), reward, done, info))
+    }
+
+    pub fn legal_action_mask(&self) -> Vec<bool> {
+        let size = self.board.size();
+        let mut mask = vec![false; size.cells()];
+        if !self.done {
+            for (row, col) in self.board.available_moves() {
+                mask[row * size.width + col] = true;
+            }
+        }
+        mask
+    }
+
+    pub fn learner(&self) -> Cell {
+        self.learner
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn observation(&self) -> Observation {
+        let cells = self.board.rows().flatten()
+            .map(|marker| match marker {
+                Cell::Empty => 0,
+                marker if *marker == self.learner => 1,
+                _ => -1,
+            })
+            .collect();
+        Observation { cells, mask: self.legal_action_mask(), board_state: self.board.board_state() }
+    }
+}
@@ -0,0 +1,205 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! The common interface of the games and of the agents that play them.
+//!
+//! Every board type plays through `Game`, so an agent written against it, like `mcts::Mcts`,
+//! plays all of them. `Agent` is what the match commands pit against each other.
+
+use crate::opponent::Opponent;
+use crate::qubic::{self, QubicBoard, QubicOpponent};
+use crate::rules::{self, RulesGame, RulesMove};
+use crate::ultimate::{UltimateBoard, UltimateOpponent};
+use crate::{Board, Cell, QLearningAgent};
+use std::fmt;
+
+/// A two-player game of alternating moves.
+pub trait Game: Clone {
+    type Move: Copy + PartialEq + fmt::Debug;
+
+    /// The marker of the player to move.
+    fn to_move(&self) -> Cell;
+    /// The moves the player to move may make; none once the game is over.
+    fn legal_moves(&self) -> Vec<Self::Move>;
+    /// Makes a legal move.
+    fn play(&mut self, action: Self::Move);
+    /// Whether the game is over, and who won it.
+    fn outcome(&self) -> (bool, Option<Cell>);
+    /// Tells positions with the same player to move apart, e.g. to find one again in a search tree.
+    fn key(&self) -> String;
+
+    /// A move that wins on the spot for the player to move. This tries every move, so games
+    /// that can find one faster should.
+    fn winning_move(&self) -> Option<Self::Move> {
+        let marker = self.to_move();
+        self.legal_moves().into_iter().find(|&action| {
+            let mut game = self.clone();
+            game.play(action);
+            game.outcome().1 == Some(marker)
+        })
+    }
+
+    /// A move that stops the opponent winning with their next one, if the game can tell.
+    fn blocking_move(&self) -> Option<Self::Move> {
+        None
+    }
+}
+
+/// A computer player.
+pub trait Agent<G: Game> {
+    /// Picks a move for the player to move; the game must not be over.
+    fn choose_move(&mut self, game: &G) -> G::Move;
+}
+
+impl Game for Board {
+    type Move = (usize, usize);
+
+    fn to_move(&self) -> Cell {
+        self.get_current_player().marker
+    }
+
+    fn legal_moves(&self) -> Vec<Self::Move> {
+        if self.is_game_over().0 { Vec::new() } else { self.available_moves() }
+    }
+
+    fn play(&mut self, action: Self::Move) {
+        self.make_move(action.0, action.1);
+    }
+
+    fn outcome(&self) -> (bool, Option<Cell>) {
+        self.is_game_over()
+    }
+
+    fn key(&self) -> String {
+        self.board_state()
+    }
+
+    fn winning_move(&self) -> Option<Self::Move> {
+        self.find_winning_move()
+    }
+
+    fn blocking_move(&self) -> Option<Self::Move> {
+        self.find_blocking_move()
+    }
+}
+
+impl Game for UltimateBoard {
+    type Move = (usize, usize);
+
+    fn to_move(&self) -> Cell {
+        self.get_current_player().marker
+    }
+
+    fn legal_moves(&self) -> Vec<Self::Move> {
+        if self.is_game_over().0 { Vec::new() } else { self.available_moves() }
+    }
+
+    fn play(&mut self, action: Self::Move) {
+        self.make_move(action.0, action.1);
+    }
+
+    fn outcome(&self) -> (bool, Option<Cell>) {
+        self.is_game_over()
+    }
+
+    fn key(&self) -> String {
+        self.board_state()
+    }
+
+    fn winning_move(&self) -> Option<Self::Move> {
+        self.find_winning_move()
+    }
+}
+
+impl Game for QubicBoard {
+    type Move = qubic::Position;
+
+    fn to_move(&self) -> Cell {
+        self.get_current_player().marker
+    }
+
+    fn legal_moves(&self) -> Vec<Self::Move> {
+        if self.is_game_over().0 { Vec::new() } else { self.available_moves() }
+    }
+
+    fn play(&mut self, action: Self::Move) {
+        self.make_move(action.0, action.1, action.2);
+    }
+
+    fn outcome(&self) -> (bool, Option<Cell>) {
+        self.is_game_over()
+    }
+
+    fn key(&self) -> String {
+        self.board_state()
+    }
+
+    fn winning_move(&self) -> Option<Self::Move> {
+        let marker = self.get_current_player().marker;
+        self.legal_moves().into_iter().find(|&pos| self.completes_line(pos, marker))
+    }
+
+    fn blocking_move(&self) -> Option<Self::Move> {
+        let marker = self.get_current_player().opponent().marker;
+        self.legal_moves().into_iter().find(|&pos| self.completes_line(pos, marker))
+    }
+}
+
+impl Game for RulesGame {
+    type Move = RulesMove;
+
+    fn to_move(&self) -> Cell {
+        self.get_current_player().marker
+    }
+
+    fn legal_moves(&self) -> Vec<Self::Move> {
+        self.available_moves()
+    }
+
+    fn play(&mut self, action: Self::Move) {
+        self.make_move(action);
+    }
+
+    fn outcome(&self) -> (bool, Option<Cell>) {
+        self.is_game_over()
+    }
+
+    fn key(&self) -> String {
+        self.board_state()
+    }
+}
+
+/// Plays greedily from its Q-table once training is off; while `train` is set it still explores.
+impl Agent<Board> for QLearningAgent {
+    fn choose_move(&mut self, game: &Board) -> (usize, usize) {
+        let (action, _, _) = self.choose_action(&game.board_state(), &game.available_moves(), None);
+        action
+    }
+}
+
+impl Agent<RulesGame> for QLearningAgent {
+    fn choose_move(&mut self, game: &RulesGame) -> RulesMove {
+        rules::choose_move(self, game).0
+    }
+}
+
+impl Agent<Board> for Opponent {
+    fn choose_move(&mut self, game: &Board) -> (usize, usize) {
+        Opponent::choose_move(self, game)
+    }
+}
+
+impl Agent<UltimateBoard> for UltimateOpponent {
+    fn choose_move(&mut self, game: &UltimateBoard) -> (usize, usize) {
+        UltimateOpponent::choose_move(self, game)
+    }
+}
+
+impl Agent<QubicBoard> for QubicOpponent {
+    fn choose_move(&mut self, game: &QubicBoard) -> qubic::Position {
+        QubicOpponent::choose_move(self, game)
+    }
+}
@@ -0,0 +1,986 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+
+//! The game, the Q-learning agent and its training, shared by the GUI and the other binaries.
+
+use rand::Rng;
+use std::collections::HashMap;
+use std::fmt;
+use rand::prelude::IndexedRandom;
+use serde::{Serialize, Deserialize};
+use convergence::{ChangeWindow, EarlyStopping, StopReason};
+use checkpoint::Checkpoints;
+use curriculum::Curriculum;
+use model::TrainingMetadata;
+use metrics::{EpisodeStats, MetricsLog};
+use opponent::{Opponent, OpponentPool};
+use replay::{ReplayBuffer, Transition};
+use rules::{Rules, RulesGame};
+
+pub mod api;
+pub mod checkpoint;
+pub mod codegen;
+pub mod convergence;
+pub mod curriculum;
+pub mod dot;
+pub mod dqn;
+pub mod engine;
+pub mod env;
+pub mod game;
+pub mod mcts;
+pub mod metrics;
+pub mod mlp;
+pub mod model;
+pub mod net;
+pub mod opponent;
+#[cfg(feature = "python")]
+mod python;
+pub mod qubic;
+pub mod random;
+pub mod replay;
+pub mod rules;
+pub mod stats;
+pub mod table;
+pub mod ultimate;
+
+pub const TRAIN_EPISODE: usize = 300000;
+pub const FILENAME: &str = "data.json";
+/// The exploration rate that epsilon decays to during training.
+pub const MIN_EPSILON: f64 = 0.1;
+/// The rows, columns and diagonals of a 3×3 board, as cell indices.
+pub(crate) const LINES: [[usize; 3]; 8] = [[0, 1, 2], [3, 4, 5], [6, 7, 8], [0, 3, 6], [1, 4, 7], [2, 5, 8], [0, 4, 8], [2, 4, 6]];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    Empty,
+    X,
+    O,
+}
+
+#[derive(Debug, Clone)]
+pub struct Player {
+    pub marker: Cell, // X or O
+}
+
+impl Player {
+    pub fn new(marker: Cell) -> Self {
+        Player { marker }
+    }
+    pub fn opponent(&self) -> Self {
+        if self.marker == Cell::X {
+            Player::new(Cell::O)
+        } else if self.marker == Cell::O {
+            Player::new(Cell::X)
+        } else { Player::new(Cell::Empty) }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MoveStatus {
+    pub move_successful: bool,
+    pub game_over: bool,
+}
+
+/// The dimensions of a board and how many marks in a row win on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardSize {
+    pub width: usize,
+    pub height: usize,
+    /// The length of a winning row, column or diagonal.
+    pub k: usize,
+}
+
+impl BoardSize {
+    /// Standard tic-tac-toe.
+    pub const CLASSIC: BoardSize = BoardSize { width: 3, height: 3, k: 3 };
+    /// The longest side allowed, enough for 15×15 gomoku and a 19×19 go board.
+    pub const MAX_SIDE: usize = 19;
+
+    pub fn new(width: usize, height: usize, k: usize) -> Result<Self, String> {
+        if !(1..=Self::MAX_SIDE).contains(&width) || !(1..=Self::MAX_SIDE).contains(&height) {
+            return Err(format!("board sides must be between 1 and {}, got {}x{}", Self::MAX_SIDE, width, height));
+        }
+        if k == 0 || k > width.max(height) {
+            return Err(format!("k must be between 1 and {} on a {}x{} board, got {}", width.max(height), width, height, k));
+        }
+        Ok(BoardSize { width, height, k })
+    }
+
+    /// Parses `WIDTHxHEIGHT[:K]`, e.g. `4x4:3`, `5x5:4` or `15x15:5`. Without `K` a line
+    /// must span the shorter side.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid board size '{}', expected WIDTHxHEIGHT[:K]", spec);
+        let (sides, k) = match spec.split_once(':') {
+            Some((sides, k)) => (sides, Some(k.parse().map_err(|_| invalid())?)),
+            None => (spec, None),
+        };
+        let (width, height) = sides.split_once(['x', 'X']).ok_or_else(invalid)?;
+        let (width, height): (usize, usize) = (width.parse().map_err(|_| invalid())?, height.parse().map_err(|_| invalid())?);
+        Self::new(width, height, k.unwrap_or(width.min(height)))
+    }
+
+    pub fn cells(&self) -> usize {
+        self.width * self.height
+    }
+}
+
+impl Default for BoardSize {
+    fn default() -> Self {
+        Self::CLASSIC
+    }
+}
+
+impl fmt::Display for BoardSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}:{}", self.width, self.height, self.k)
+    }
+}
+
+/// A board of any `BoardSize`. The winner is found as moves are made, by looking along the
+/// lines through each new mark, rather than by scanning the whole board.
+#[derive(Debug, Clone)]
+pub struct Board {
+    /// The cells row by row.
+    cells: Vec<Cell>,
+    players: [Player; 2],
+    current_player: usize,
+    size: BoardSize,
+    winner: Option<Cell>,
+    empty: usize,
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Cell::Empty => write!(f, "-"),
+            Cell::X => write!(f, "X"),
+            Cell::O => write!(f, "O"),
+        }
+    }
+}
+
+impl MoveStatus {
+    pub fn new(move_successful: bool, game_over: bool) -> Self {
+        MoveStatus {
+            move_successful,
+            game_over
+        }
+    }
+}
+
+impl Board {
+    pub fn new() -> Self {
+        Self::with_size(BoardSize::CLASSIC)
+    }
+    pub fn with_size(size: BoardSize) -> Self {
+        Board {
+            cells: vec![Cell::Empty; size.cells()],
+            players: [Player { marker: Cell::X }, Player { marker: Cell::O }],
+            current_player: random::rng().random_range(0..=1),
+            size,
+            winner: None,
+            empty: size.cells(),
+        }
+    }
+    /// Rebuilds a 3×3 board from a `board_state` string. The side to move follows from the
+    /// marker counts; if they are equal either side may start, so it is picked at random as in `new`.
+    pub fn from_state(state: &str) -> Option<Self> {
+        Self::from_state_with_size(BoardSize::CLASSIC, state)
+    }
+    /// Like `from_state`, for a board of the given size.
+    pub fn from_state_with_size(size: BoardSize, state: &str) -> Option<Self> {
+        let cells = state
+            .chars()
+            .map(|c| match c {
+                'X' => Some(Cell::X),
+                'O' => Some(Cell::O),
+                '-' => Some(Cell::Empty),
+                _ => None,
+            })
+            .collect::<Option<Vec<Cell>>>()?;
+        if cells.len() != size.cells() {
+            return None;
+        }
+        let mut board = Board::with_size(size);
+        board.cells = cells;
+        board.empty = board.cells.iter().filter(|&&c| c == Cell::Empty).count();
+        board.winner = (0..size.cells())
+            .map(|i| (i / size.width, i % size.width))
+            .find(|&(row, col)| {
+                let marker = board.cell(row, col);
+                marker != Cell::Empty && board.completes_line(row, col, marker)
+            })
+            .map(|(row, col)| board.cell(row, col));
+        let x_count = board.cells.iter().filter(|&&c| c == Cell::X).count();
+        let o_count = board.cells.iter().filter(|&&c| c == Cell::O).count();
+        if x_count == o_count + 1 {
+            board.current_player = 1;
+        } else if o_count == x_count + 1 {
+            board.current_player = 0;
+        } else if x_count != o_count {
+            return None;
+        }
+        Some(board)
+    }
+    pub fn size(&self) -> BoardSize {
+        self.size
+    }
+    pub fn cell(&self, row: usize, col: usize) -> Cell {
+        self.cells[row * self.size.width + col]
+    }
+    /// The cells, one row at a time.
+    pub fn rows(&self) -> std::slice::Chunks<'_, Cell> {
+        self.cells.chunks(self.size.width)
+    }
+    pub fn get_current_player(&self) -> &Player {
+        &self.players[self.current_player]
+    }
+    /// Gives the move to the player of `marker`, e.g. to pick who starts.
+    pub fn set_current_player(&mut self, marker: Cell) {
+        self.current_player = if marker == Cell::O { 1 } else { 0 };
+    }
+    pub fn make_move(&mut self, row: usize, col: usize) -> (MoveStatus, Option<Cell>) {
+        self.place(row, col, self.players[self.current_player].marker)
+    }
+    /// Like `make_move`, but places `marker` whoever's turn it is, for rule variants in which
+    /// the players share marks. The winner is the marker of the first completed line.
+    pub fn place(&mut self, row: usize, col: usize, marker: Cell) -> (MoveStatus, Option<Cell>) {
+        if row < self.size.height && col < self.size.width && self.cell(row, col) == Cell::Empty {
+            self.cells[row * self.size.width + col] = marker;
+            self.empty -= 1;
+            if self.winner.is_none() && self.completes_line(row, col, marker) {
+                self.winner = Some(marker);
+            }
+            self.switch_turn();
+            let (game_over, winner) = self.is_game_over();
+            (MoveStatus::new(true, game_over), winner)
+        } else {
+            (MoveStatus::new(false, false), None)
+        }
+    }
+    pub fn switch_turn(&mut self) {
+        self.current_player = 1 - self.current_player;
+    }
+    /// Whether `marker` at `row`, `col` would complete a line of `k`, whatever the cell holds now.
+    pub fn completes_line(&self, row: usize, col: usize, marker: Cell) -> bool {
+        let run = |row_step: isize, col_step: isize| {
+            let (mut row, mut col) = (row as isize + row_step, col as isize + col_step);
+            let mut length = 0;
+            while (0..self.size.height as isize).contains(&row)
+                && (0..self.size.width as isize).contains(&col)
+                && self.cell(row as usize, col as usize) == marker
+            {
+                length += 1;
+                row += row_step;
+                col += col_step;
+            }
+            length
+        };
+        [(0, 1), (1, 0), (1, 1), (1, -1)]
+            .into_iter()
+            .any(|(row_step, col_step)| 1 + run(row_step, col_step) + run(-row_step, -col_step) >= self.size.k)
+    }
+    pub fn check_winner(&self) -> Option<Cell> {
+        self.winner
+    }
+
+    pub fn is_draw(&self) -> bool {
+        self.empty == 0
+    }
+
+    pub fn is_game_over(&self) -> (bool, Option<Cell>) {
+        let winner = self.check_winner();
+        (winner.is_some() || self.is_draw(), winner)
+    }
+
+    pub fn board_state(&self) -> String {
+        self.cells.iter().map(|&x| x.to_string()).collect()
+    }
+
+    pub fn available_moves(&self) -> Vec<(usize, usize)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|&(_, &cell)| cell == Cell::Empty)
+            .map(|(i, _)| (i / self.size.width, i % self.size.width))
+            .collect()
+    }
+    pub fn find_winning_move(&self) -> Option<(usize, usize)> {
+        let current_player_marker = self.get_current_player().marker;
+        self.available_moves()
+            .into_iter()
+            .find(|&(row, col)| self.completes_line(row, col, current_player_marker))
+    }
+    pub fn find_blocking_move(&self) -> Option<(usize, usize)> {
+        let opponent_marker = self.get_current_player().opponent().marker;
+        self.available_moves()
+            .into_iter()
+            .find(|&(row, col)| self.completes_line(row, col, opponent_marker))
+    }
+    pub fn reset(&mut self) {
+        let mut rng = random::rng();
+        self.cells.fill(Cell::Empty);
+        self.winner = None;
+        self.empty = self.size.cells();
+        self.current_player = rng.random_range(0..=1);
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.board_state())
+    }
+}
+
+/// A single state-action value together with the statistics gathered while learning it.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(from = "QEntryRepr")]
+pub struct QEntry {
+    pub value: f64,
+    pub visits: u64,
+    pub last_episode: usize,
+    /// Exponential moving average of the absolute TD error.
+    pub td_error: f64,
+}
+
+/// Models saved before entries carried statistics store a bare value.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum QEntryRepr {
+    Value(f64),
+    Entry {
+        value: f64,
+        visits: u64,
+        last_episode: usize,
+        td_error: f64,
+    },
+}
+
+impl From<QEntryRepr> for QEntry {
+    fn from(repr: QEntryRepr) -> Self {
+        match repr {
+            QEntryRepr::Value(value) => QEntry { value, ..Default::default() },
+            QEntryRepr::Entry { value, visits, last_episode, td_error } => {
+                QEntry { value, visits, last_episode, td_error }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum AlphaSchedule {
+    /// Always learn with the agent's `alpha`.
+    #[default]
+    Constant,
+    /// Learn with `1 / n`, where `n` is the number of updates of the entry.
+    InverseVisits,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Exploration {
+    /// A random move with probability `epsilon`.
+    #[default]
+    EpsilonGreedy,
+    /// Upper confidence bound: untried moves first, then `Q + c * sqrt(ln N / n)`.
+    Ucb { c: f64 },
+}
+
+#[derive(Debug,Clone,Serialize,Deserialize)]
+pub struct QLearningAgent {
+    pub(crate) q_table: HashMap<String, HashMap<String, QEntry>>,
+    pub(crate) alpha: f64,
+    pub(crate) gamma: f64,
+    pub(crate) epsilon: f64,
+    /// Whether the agent is being trained; this is never saved, so loaded agents play greedily.
+    #[serde(skip)]
+    pub(crate) train: bool,
+    #[serde(default)]
+    pub(crate) alpha_schedule: AlphaSchedule,
+    #[serde(default)]
+    pub(crate) exploration: Exploration,
+    /// The training episode in progress, recorded in the entries it updates.
+    #[serde(default)]
+    pub(crate) episode: usize,
+    /// Q-value changes since the last convergence check.
+    #[serde(skip)]
+    pub(crate) changes: ChangeWindow,
+    /// Saved in the model header rather than with the Q-table.
+    #[serde(skip)]
+    pub(crate) metadata: TrainingMetadata,
+}
+
+impl QLearningAgent {
+    pub fn new(alpha: f64, gamma: f64, epsilon: f64) -> Self {
+        QLearningAgent {
+            q_table: HashMap::new(),
+            alpha,
+            gamma,
+            epsilon,
+            train: true,
+            alpha_schedule: AlphaSchedule::Constant,
+            exploration: Exploration::EpsilonGreedy,
+            episode: 0,
+            changes: ChangeWindow::default(),
+            metadata: TrainingMetadata::default(),
+        }
+    }
+    pub fn q_table(&self) -> &HashMap<String, HashMap<String, QEntry>> {
+        &self.q_table
+    }
+    pub fn metadata(&self) -> &TrainingMetadata {
+        &self.metadata
+    }
+    /// Turns exploration and forced blocks on or off; `train_q_learning` turns them on itself.
+    pub fn set_training(&mut self, train: bool) {
+        self.train = train;
+    }
+    pub fn set_alpha_schedule(&mut self, alpha_schedule: AlphaSchedule) {
+        self.alpha_schedule = alpha_schedule;
+    }
+    pub fn set_exploration(&mut self, exploration: Exploration) {
+        self.exploration = exploration;
+    }
+    /// Applies a Q-learning update and returns the TD error.
+    pub fn update_q_value(&mut self, state: &str, action: &str, reward: f64, next_state: &str) -> f64 {
+        let max_q_next = self
+            .q_table
+            .get(next_state)
+            .map(|actions| actions.values().map(|entry| entry.value).fold(f64::NEG_INFINITY, f64::max))
+            .unwrap_or(0.0);
+        self.update_towards(state, action, reward + self.gamma * max_q_next)
+    }
+
+    /// Moves the value of `action` in `state` towards `target` and returns the TD error.
+    pub fn update_towards(&mut self, state: &str, action: &str, target: f64) -> f64 {
+        let episode = self.episode;
+        let entry = self
+            .q_table
+            .entry(state.to_string())
+            .or_default()
+            .entry(action.to_string())
+            .or_default();
+        entry.visits += 1;
+        let alpha = match self.alpha_schedule {
+            AlphaSchedule::Constant => self.alpha,
+            AlphaSchedule::InverseVisits => 1.0 / entry.visits as f64,
+        };
+        let td_error = target - entry.value;
+        entry.value += alpha * td_error;
+        entry.last_episode = episode;
+        entry.td_error = if entry.visits == 1 { td_error.abs() } else { 0.9 * entry.td_error + 0.1 * td_error.abs() };
+        self.changes.record(alpha * td_error, alpha);
+        td_error
+    }
+
+    pub fn choose_action(&mut self, state: &str, available_moves: &[(usize, usize)], blocking_move: Option<(usize, usize)>) -> ((usize, usize),bool,bool) {
+        if let Some(blocking_move) = blocking_move.filter(|_| self.train) {
+            (blocking_move, true, false)
+        } else {
+        let mut rng = random::rng();
+        if let Exploration::Ucb { c } = self.exploration
+            && self.train
+        {
+            let action = self.ucb_action(state, available_moves, c);
+            (action, false, Some(action) != self.greedy_action(state, available_moves))
+        } else if (rng.random::<f64>() < self.epsilon) && self.train {
+            (*available_moves.choose(&mut rng).unwrap(),false, true)
+        } else {
+            if let Some(best_action) = self.greedy_action(state, available_moves) {
+                (best_action, false, false)
+            } else {
+                (*available_moves.choose(&mut rng).unwrap(),false, false)
+            }
+        }
+        }
+    }
+    pub fn ucb_action(&self, state: &str, available_moves: &[(usize, usize)], c: f64) -> (usize, usize) {
+        let mut rng = random::rng();
+        let entry = |pos: &(usize, usize)| {
+            self.q_table
+                .get(state)
+                .and_then(|actions| actions.get(&format!("{},{}", pos.0, pos.1)))
+                .copied()
+                .unwrap_or_default()
+        };
+        let untried: Vec<(usize, usize)> = available_moves.iter().copied().filter(|pos| entry(pos).visits == 0).collect();
+        if let Some(action) = untried.choose(&mut rng) {
+            return *action;
+        }
+        let total_visits: u64 = available_moves.iter().map(|pos| entry(pos).visits).sum();
+        let score = |pos: &(usize, usize)| {
+            let entry = entry(pos);
+            entry.value + c * ((total_visits as f64).ln() / entry.visits as f64).sqrt()
+        };
+        *available_moves
+            .iter()
+            .max_by(|a, b| score(a).partial_cmp(&score(b)).unwrap())
+            .unwrap()
+    }
+    /// The highest valued move in `state`, or `None` if the state has never been visited.
+    pub fn greedy_action(&self, state: &str, available_moves: &[(usize, usize)]) -> Option<(usize, usize)> {
+        let actions = self.q_table.get(state)?;
+        available_moves
+            .iter()
+            .max_by(|&a, &b| {
+                let str_a = format!("{},{}", a.0,a.1);
+                let str_b = format!("{},{}", b.0,b.1);
+                let q_a = actions.get(&str_a).map_or(0.0, |entry| entry.value);
+                let q_b = actions.get(&str_b).map_or(0.0, |entry| entry.value);
+                q_a.partial_cmp(&q_b).unwrap()
+            })
+            .copied()
+    }
+    /// Drops the entries updated fewer than `min_visits` times, and the states left without
+    /// any. Returns the number of entries removed.
+    pub fn prune(&mut self, min_visits: u64) -> usize {
+        let before: usize = self.q_table.values().map(HashMap::len).sum();
+        for actions in self.q_table.values_mut() {
+            actions.retain(|_, entry| entry.visits >= min_visits);
+        }
+        self.q_table.retain(|_, actions| !actions.is_empty());
+        before - self.q_table.values().map(HashMap::len).sum::<usize>()
+    }
+    pub fn save_to_path(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        model::save(self, path)
+    }
+    pub fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        model::load(path)
+    }
+
+}
+
+/// The rewards for the end of a game, from the side of the learner; the network agent of `dqn`
+/// learns from them too.
+pub(crate) const WIN_REWARD: f64 = 1.0;
+pub(crate) const LOSS_REWARD: f64 = -1.0;
+pub(crate) const DRAW_REWARD: f64 = 0.3;
+
+fn blocking_reward(state: &str) -> f64 {
+    // more than five of the nine cells empty, or the same share of a larger board
+    let empty_cells = state.chars().filter(|&c| c == '-').count();
+    if empty_cells * 9 > state.len() * 5 { 0.9 } else { 0.4 }
+}
+
+/// Learns from a transition and, if a replay buffer is in use, stores it and replays older ones.
+fn learn(agent: &mut QLearningAgent, replay: Option<&mut ReplayBuffer>, transition: Transition) {
+    let td_error = transition.apply(agent);
+    if let Some(replay) = replay {
+        replay.push(transition, td_error);
+        replay.replay(agent);
+    }
+}
+
+/// Plays one episode with the agent on both sides.
+fn self_play_episode(agent: &mut QLearningAgent, mut replay: Option<&mut ReplayBuffer>, mut game: Board) -> EpisodeStats {
+    let mut stats = EpisodeStats::default();
+    let mut state_history: Vec<String> = Vec::new();
+    let mut action_history: Vec<String> = Vec::new();
+    loop {
+        let current_player = game.get_current_player().clone();
+        let prev_player = current_player.opponent();
+        let (game_over,winner) = game.is_game_over();
+        if game_over {
+            // if we win the game we propagate reward to the previous state space;
+            // games started from a curriculum position may be too short to have one
+            action_history.pop();
+            if winner.unwrap_or(Cell::Empty) == prev_player.marker && !action_history.is_empty() {
+                let mut propagation_reward = 0.7;
+                let decay_percentage = 0.7;
+                let action_val = action_history.pop().unwrap();
+                //assert!(action.is_some());
+                for states in state_history.windows(2) {
+                    agent.update_q_value(&states[0],&action_val,propagation_reward,&states[1]);
+                    propagation_reward = (agent.alpha * decay_percentage).min(0.1);
+                }
+            }
+            stats.winner = winner;
+            break;
+        };
+        let state = game.board_state();
+        state_history.push(state.clone());
+        let moves = game.available_moves();
+        let blocking_move = game.find_blocking_move();
+        let (action,is_blocking_move,explore) = agent.choose_action(&state, &moves, blocking_move);
+        stats.record_move(explore, is_blocking_move);
+        let action_hash = format!("{},{}", action.0, action.1);
+        game.make_move(action.0,action.1);
+        action_history.push(action_hash.clone());
+        let reward = if game.check_winner().unwrap_or(Cell::Empty) == current_player.marker { WIN_REWARD }
+                            else if is_blocking_move {blocking_reward(&state)}
+                            else if game.is_draw(){DRAW_REWARD}
+                            else {0.0};
+        let next_state = game.board_state();
+        let done = game.is_game_over().0;
+        learn(agent, replay.as_deref_mut(), Transition { state, action: action_hash, reward, next_state, done });
+    }
+    stats
+}
+
+/// Plays one episode with the agent on a random side against `opponent`. Only the agent's
+/// moves are learned from: each of them is updated once the opponent has replied, and a
+/// lost game is punished.
+fn opponent_episode(agent: &mut QLearningAgent, mut replay: Option<&mut ReplayBuffer>, opponent: &mut Opponent, mut game: Board) -> EpisodeStats {
+    let agent_marker = if random::rng().random_bool(0.5) { Cell::X } else { Cell::O };
+    let mut stats = EpisodeStats { agent_side: Some(agent_marker), ..Default::default() };
+    // the agent's last (state, action, reward), waiting for the opponent's reply
+    let mut pending: Option<(String, String, f64)> = None;
+    loop {
+        let (game_over, winner) = game.is_game_over();
+        if game_over {
+            if let Some((state, action, reward)) = pending.take() {
+                let reward = match winner {
+                    Some(marker) if marker == agent_marker => WIN_REWARD,
+                    Some(_) => LOSS_REWARD,
+                    None => reward.max(DRAW_REWARD),
+                };
+                learn(agent, replay.as_deref_mut(), Transition { state, action, reward, next_state: game.board_state(), done: true });
+            }
+            stats.winner = winner;
+            break;
+        }
+        if game.get_current_player().marker != agent_marker {
+            let action = opponent.choose_move(&game);
+            game.make_move(action.0, action.1);
+            stats.moves += 1;
+            continue;
+        }
+        let state = game.board_state();
+        if let Some((prev_state, prev_action, reward)) = pending.take() {
+            learn(agent, replay.as_deref_mut(), Transition { state: prev_state, action: prev_action, reward, next_state: state.clone(), done: false });
+        }
+        let moves = game.available_moves();
+        let blocking_move = game.find_blocking_move();
+        let (action, is_blocking_move, explore) = agent.choose_action(&state, &moves, blocking_move);
+        stats.record_move(explore, is_blocking_move);
+        game.make_move(action.0, action.1);
+        let reward = if is_blocking_move { blocking_reward(&state) } else { 0.0 };
+        pending = Some((state, format!("{},{}", action.0, action.1), reward));
+    }
+    stats
+}
+
+/// Everything about a training run apart from the agent's own hyperparameters.
+pub struct TrainingOptions {
+    pub episodes: usize,
+    /// The board to train on. Curricula and the minimax opponent only work on the 3×3 board.
+    pub board: BoardSize,
+    /// Anything but the standard rules is learned by self-play alone, without curricula,
+    /// opponents or replay.
+    pub rules: Rules,
+    pub opponents: OpponentPool,
+    pub curriculum: Option<Curriculum>,
+    pub replay: Option<ReplayBuffer>,
+    pub early_stopping: Option<EarlyStopping>,
+    pub metrics: Option<MetricsLog>,
+    /// Seeds the random number generator; a random seed is drawn (and saved) if unset.
+    pub seed: Option<u64>,
+    /// Where the trained model is saved, if anywhere.
+    pub output: Option<String>,
+    pub checkpoints: Option<Checkpoints>,
+    /// Continue the run a checkpointed agent was saved from, starting after its
+    /// `episode` counter and decaying epsilon from its initial value.
+    pub resume: bool,
+}
+
+impl TrainingOptions {
+    pub fn new(episodes: usize) -> Self {
+        TrainingOptions {
+            episodes,
+            board: BoardSize::CLASSIC,
+            rules: Rules::Standard,
+            opponents: OpponentPool::self_play(),
+            curriculum: None,
+            replay: None,
+            early_stopping: None,
+            metrics: None,
+            seed: None,
+            output: Some(FILENAME.to_string()),
+            checkpoints: None,
+            resume: false,
+        }
+    }
+}
+
+pub fn train_q_learning(agent: &mut QLearningAgent, options: &mut TrainingOptions) {
+    let mut exploration: i64 = 0;
+    let mut exploitation: i64 = 0;
+    let min_epsilon: f64 = MIN_EPSILON;
+    let episodes = options.episodes;
+    let (start, epsilon_start) = if options.resume {
+        (agent.episode.min(episodes), agent.metadata.initial_epsilon.unwrap_or(agent.epsilon))
+    } else {
+        (0, agent.epsilon)
+    };
+    // a resumed run reseeds past the interrupted one rather than replaying its random numbers
+    let seed = options.seed.or(agent.metadata.seed.filter(|_| options.resume)).unwrap_or_else(rand::random);
+    random::seed(seed.wrapping_add(start as u64));
+    agent.metadata.seed = Some(seed);
+    agent.metadata.board = options.board;
+    agent.metadata.rules = options.rules;
+    agent.metadata.initial_epsilon.get_or_insert(epsilon_start);
+    // loaded agents, resumed checkpoints among them, come back with training off
+    agent.train = true;
+    let played_before = agent.metadata.episodes.saturating_sub(start);
+    if start > 0 {
+        println!("Resuming at episode {} of {}", start, episodes);
+    }
+    let mut stop = (episodes, StopReason::Completed);
+    // runs without stopping criteria are still summarised
+    let mut summary_only = None;
+    let early_stopping = match options.early_stopping.as_mut() {
+        Some(early_stopping) => early_stopping,
+        None => summary_only.insert(EarlyStopping::never()),
+    };
+    for episode in start..episodes {
+        agent.episode = episode;
+        if episode > 0 {
+            options.opponents.refresh(agent, episode);
+        }
+        let mut game = Board::with_size(options.board);
+        if let Some(curriculum) = options.curriculum.as_mut() {
+            if episode > 0 && episode.is_multiple_of(curriculum.review_interval) {
+                curriculum.review(agent);
+            }
+            game = curriculum.start_position(episode, episodes).unwrap_or(game);
+        }
+        let opponent = options.opponents.sample();
+        let stats = if options.rules != Rules::Standard {
+            rules::self_play_episode(agent, RulesGame::new(options.rules, options.board))
+        } else if let Opponent::SelfPlay = opponent {
+            self_play_episode(agent, options.replay.as_mut(), game)
+        } else {
+            opponent_episode(agent, options.replay.as_mut(), opponent, game)
+        };
+        exploration += stats.explored;
+        exploitation += stats.exploited;
+        // decays over the whole run, however long, counting the episodes a resumed run skipped
+        let progress = (episode + 1) as f64 / episodes as f64;
+        agent.epsilon = (epsilon_start - progress * (epsilon_start - min_epsilon)).max(min_epsilon);
+        let changes = std::mem::take(&mut agent.changes);
+        if let Some(metrics) = options.metrics.as_mut()
+            && let Err(err) = metrics.record(episode + 1, agent, &stats, &changes)
+        {
+            eprintln!("Stopped writing metrics: {}", err);
+            options.metrics = None;
+        }
+        if let Some(reason) = early_stopping.check(agent, &changes, episode + 1) {
+            stop = (episode + 1, reason);
+            break;
+        }
+        if let Some(checkpoints) = options.checkpoints.as_mut() {
+            agent.episode = episode + 1;
+            agent.metadata.episodes = played_before + episode + 1;
+            match checkpoints.save(agent, episode + 1) {
+                Ok(Some(path)) => println!("Saved checkpoint {}", path.display()),
+                Ok(None) => {}
+                Err(err) => eprintln!("Failed to save checkpoint: {}", err),
+            }
+        }
+    }
+    agent.episode = stop.0;
+    let total_loop = exploration + exploitation;
+    let mut summary = early_stopping.summary(stop.0, stop.1);
+    summary.exploration = Some(exploration as f64 / total_loop.max(1) as f64);
+    println!("{}", summary);
+    agent.metadata.summary = Some(summary);
+    agent.metadata.episodes = played_before + stop.0;
+    if let Some(metrics) = options.metrics.as_mut()
+        && let Err(err) = metrics.finish(stop.0, agent)
+    {
+        eprintln!("Failed to write metrics: {}", err);
+    }
+    if let Some(output) = &options.output {
+        match agent.save_to_path(output) {
+            Ok(()) => println!("Saved game data to {}", output),
+            Err(err) => eprintln!("Failed to save game data: {}", err),
+        }
+    }
+    println!("Exploration: {:.2}, Exploitation: {:.2}", (exploration as f64)/(total_loop as f64), (exploitation as f64)/(total_loop as f64));
+    if let Some(curriculum) = &options.curriculum {
+        println!("Curriculum positions: {}", curriculum.len());
+    }
+    if let Some(replay) = &options.replay {
+        println!("Replay buffer transitions: {}", replay.len());
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quiet_options(episodes: usize) -> TrainingOptions {
+        TrainingOptions { seed: Some(1), output: None, ..TrainingOptions::new(episodes) }
+    }
+
+    fn state_with(size: BoardSize, x: &[(usize, usize)], o: &[(usize, usize)]) -> String {
+        let mut state = vec!['-'; size.cells()];
+        for &(row, col) in x {
+            state[row * size.width + col] = 'X';
+        }
+        for &(row, col) in o {
+            state[row * size.width + col] = 'O';
+        }
+        state.into_iter().collect()
+    }
+
+    #[test]
+    fn lines_of_k_win_on_larger_boards() {
+        for spec in ["4x4:3", "5x5:4", "15x15:5"] {
+            let size = BoardSize::parse(spec).unwrap();
+            // k - 1 O's in the top row, where no line below reaches
+            let o: Vec<_> = (0..size.k - 1).map(|col| (0, col)).collect();
+            for (row_step, col_step) in [(0, 1), (1, 0), (1, 1), (1, -1)] {
+                // each line ends on the bottom or right edge
+                let start_col = if col_step < 0 { size.width - 1 } else { size.width - size.k };
+                let line: Vec<(usize, usize)> = (0..size.k)
+                    .map(|i| (size.height - size.k + i * row_step, (start_col as isize + i as isize * col_step) as usize))
+                    .collect();
+                let (&(row, col), x) = line.split_last().unwrap();
+                let board = Board::from_state_with_size(size, &state_with(size, x, &o)).unwrap();
+                assert_eq!(board.is_game_over(), (false, None), "{} {:?}", spec, line);
+                assert!(board.completes_line(row, col, Cell::X), "{} {:?}", spec, line);
+                assert!(!board.completes_line(row, col, Cell::O), "{} {:?}", spec, line);
+                let short = Board::from_state_with_size(size, &state_with(size, &x[1..], &o[1..])).unwrap();
+                assert!(!short.completes_line(row, col, Cell::X), "{} {:?}", spec, line);
+                let won = Board::from_state_with_size(size, &state_with(size, &line, &o)).unwrap();
+                assert_eq!(won.is_game_over(), (true, Some(Cell::X)), "{} {:?}", spec, line);
+            }
+        }
+    }
+
+    #[test]
+    fn states_must_fit_the_board() {
+        for spec in ["4x4:3", "5x5:4", "15x15:5"] {
+            let size = BoardSize::parse(spec).unwrap();
+            let empty = Board::from_state_with_size(size, &"-".repeat(size.cells())).unwrap();
+            assert_eq!((empty.size(), empty.available_moves().len()), (size, size.cells()));
+            assert!(Board::from_state_with_size(size, "---------").is_none());
+            assert!(Board::from_state_with_size(size, &"-".repeat(size.cells() + 1)).is_none());
+            assert!(Board::from_state_with_size(size, &state_with(size, &[(0, 0), (1, 1)], &[])).is_none());
+            let to_move = Board::from_state_with_size(size, &state_with(size, &[(1, 1)], &[])).unwrap();
+            assert_eq!(to_move.get_current_player().marker, Cell::O);
+        }
+    }
+
+    #[test]
+    fn inverse_visits_learn_the_mean_target() {
+        let mut agent = QLearningAgent::new(0.5, 0.9, 0.1);
+        agent.alpha_schedule = AlphaSchedule::InverseVisits;
+        let targets = [1.0, -0.5, 0.25, 0.75, 0.0];
+        for (n, &target) in targets.iter().enumerate() {
+            agent.update_towards("---------", "1,1", target);
+            let mean = targets[..=n].iter().sum::<f64>() / (n + 1) as f64;
+            assert!((agent.q_table["---------"]["1,1"].value - mean).abs() < 1e-12);
+        }
+        assert_eq!(agent.q_table["---------"]["1,1"].visits, 5);
+    }
+
+    #[test]
+    fn ucb_tries_unvisited_moves_first() {
+        let mut agent = QLearningAgent::new(0.5, 0.9, 0.1);
+        let moves = [(0, 0), (1, 1), (2, 2)];
+        for _ in 0..10 {
+            agent.update_towards("---------", "0,0", 1.0);
+            agent.update_towards("---------", "1,1", 1.0);
+        }
+        // the only untried move wins over well-valued ones, then the bound favours the least visited
+        assert_eq!(agent.ucb_action("---------", &moves, 1.0), (2, 2));
+        agent.update_towards("---------", "2,2", 0.0);
+        assert_eq!(agent.ucb_action("---------", &moves, 10.0), (2, 2));
+        assert_ne!(agent.ucb_action("---------", &moves, 0.0), (2, 2));
+    }
+
+    #[test]
+    fn prune_drops_rarely_visited_entries_and_empty_states() {
+        let mut agent = QLearningAgent::new(0.5, 0.9, 0.1);
+        for (state, action, visits) in [("---------", "0,0", 1), ("---------", "1,1", 3), ("X--------", "1,1", 2)] {
+            for _ in 0..visits {
+                agent.update_towards(state, action, 1.0);
+            }
+        }
+        assert_eq!(agent.prune(3), 2);
+        assert_eq!(agent.q_table.len(), 1);
+        assert_eq!(agent.q_table["---------"].keys().collect::<Vec<_>>(), ["1,1"]);
+        assert_eq!(agent.prune(3), 0);
+    }
+
+    #[test]
+    fn tables_of_bare_values_still_load() {
+        let json = r#"{"q_table": {"---------": {"1,1": 0.5, "0,0": {"value": 0.25, "visits": 4, "last_episode": 9, "td_error": 0.1}}},
+            "alpha": 0.1, "gamma": 0.9, "epsilon": 0.3}"#;
+        let agent: QLearningAgent = serde_json::from_str(json).unwrap();
+        let (bare, full) = (agent.q_table["---------"]["1,1"], agent.q_table["---------"]["0,0"]);
+        assert_eq!((bare.value, bare.visits, bare.last_episode, bare.td_error), (0.5, 0, 0, 0.0));
+        assert_eq!((full.value, full.visits, full.last_episode, full.td_error), (0.25, 4, 9, 0.1));
+        assert_eq!((agent.alpha_schedule, agent.exploration), (AlphaSchedule::Constant, Exploration::EpsilonGreedy));
+    }
+
+    #[test]
+    fn every_run_is_summarised() {
+        let mut agent = QLearningAgent::new(0.08, 0.7, 0.9);
+        train_q_learning(&mut agent, &mut quiet_options(1500));
+        let summary = agent.metadata.summary.expect("no summary saved");
+        assert_eq!(summary.episodes, 1500);
+        assert_eq!(summary.stop_reason, StopReason::Completed);
+        assert!(summary.max_change > 0.0 && summary.mean_change > 0.0);
+    }
+
+    #[test]
+    fn epsilon_decays_over_the_run() {
+        for episodes in [500, 2000] {
+            let mut agent = QLearningAgent::new(0.08, 0.7, 0.9);
+            let halfway = std::rc::Rc::new(std::cell::Cell::new(None));
+            let mut options = quiet_options(episodes);
+            let seen = halfway.clone();
+            options.metrics = Some(MetricsLog::callback(episodes / 2, move |row| {
+                if seen.get().is_none() {
+                    seen.set(Some(row.epsilon));
+                }
+            }));
+            train_q_learning(&mut agent, &mut options);
+            assert!((halfway.get().unwrap() - 0.5).abs() < 1e-9, "{:?} halfway through {} episodes", halfway.get(), episodes);
+            assert!((agent.epsilon - MIN_EPSILON).abs() < 1e-9, "{} after {} episodes", agent.epsilon, episodes);
+        }
+    }
+
+    #[test]
+    fn resumed_run_continues_the_interrupted_one() {
+        let directory = std::env::temp_dir().join(format!("q-learning-tictactoe-resume-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let output = directory.join("model.json").to_string_lossy().into_owned();
+
+        let mut agent = QLearningAgent::new(0.08, 0.7, 0.9);
+        let mut options = TrainingOptions { checkpoints: Some(Checkpoints::new(&output, 1000, 5)), ..quiet_options(2000) };
+        train_q_learning(&mut agent, &mut options);
+
+        let checkpoint = directory.join("model-1000.json").to_string_lossy().into_owned();
+        let mut resumed = QLearningAgent::load_from_file(&checkpoint).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(!resumed.train);
+        assert_eq!(resumed.episode, 1000);
+        assert_eq!(resumed.metadata.episodes, 1000);
+        train_q_learning(&mut resumed, &mut TrainingOptions { resume: true, ..quiet_options(3000) });
+
+        let mut uninterrupted = QLearningAgent::new(0.08, 0.7, 0.9);
+        train_q_learning(&mut uninterrupted, &mut quiet_options(3000));
+        assert_eq!(resumed.episode, 3000);
+        assert_eq!(resumed.metadata.episodes, 3000);
+        assert_eq!(resumed.epsilon, uninterrupted.epsilon);
+        assert_eq!(resumed.metadata.initial_epsilon, Some(0.9));
+        let summary = resumed.metadata.summary.unwrap();
+        assert_eq!(summary.episodes, 3000);
+        // a resumed agent that never explored would report 0.00 here
+        let (resumed_exploration, exploration) = (summary.exploration.unwrap(), uninterrupted.metadata.summary.unwrap().exploration.unwrap());
+        assert!(resumed_exploration > 0.5 * exploration, "{} vs {}", resumed_exploration, exploration);
+    }
+}
@@ -4,399 +4,574 @@
 // This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
 // See the LICENSE file for details.
 
+
 use iced::{
     time, alignment, executor, Application, Element,
     Length, Settings, Subscription, Theme, Command
 };
 use iced::widget::{
-    button, container, Column, Row, row, text
+    button, canvas, container, pick_list, Column, Row, row, text, text_input
 };
-use rand::Rng;
-use std::collections::HashMap;
-use std::default::Default;
-use std::{fmt, fs};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
 use std::time::{Duration, Instant};
-use rand::prelude::IndexedRandom;
-use serde::{Serialize, Deserialize};
-use serde_json;
+use q_learning_tictactoe::convergence::EarlyStopping;
+use q_learning_tictactoe::checkpoint::Checkpoints;
+use q_learning_tictactoe::curriculum::Curriculum;
+use q_learning_tictactoe::dqn::{self, train_dqn, DqnAgent};
+use q_learning_tictactoe::game::{Agent, Game};
+use q_learning_tictactoe::mcts::Mcts;
+use q_learning_tictactoe::metrics::{self, MetricsLog, MetricsRow};
+use q_learning_tictactoe::net::{self, Connection, NetEvent, NetMessage};
+use q_learning_tictactoe::opponent::{self, Opponent};
+use q_learning_tictactoe::opponent::OpponentPool;
+use q_learning_tictactoe::replay::{ReplayBuffer, Sampling};
+use q_learning_tictactoe::rules::{self, Piece, Rules, RulesGame, RulesMove};
+use q_learning_tictactoe::table::{self, MergeStrategy};
+use q_learning_tictactoe::qubic::{self, QubicBoard, QubicOpponent};
+use q_learning_tictactoe::ultimate::{UltimateBoard, UltimateOpponent};
+use q_learning_tictactoe::{codegen, dot, engine, model, random, stats};
+use q_learning_tictactoe::{
+    train_q_learning, AlphaSchedule, Board, BoardSize, Cell, Exploration, QLearningAgent, TrainingOptions, FILENAME,
+    TRAIN_EPISODE,
+};
 
-const TRAIN_EPISODE: usize = 300000;
-const FILENAME: &str = "data.json";
+mod chart;
+mod terminal;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Cell {
-    Empty,
-    X,
-    O,
-}
+/// Episodes between two points of the GUI's learning curves.
+const CHART_INTERVAL: usize = 2000;
+/// The height of the 3×3 grid in the GUI; larger boards shrink their cells to fit it.
+const GRID_HEIGHT: f32 = 250.0;
+const MAX_CELL: f32 = 80.0;
+const MIN_CELL: f32 = 24.0;
+const WINDOW_HEIGHT: f32 = 730.0;
 
-#[derive(Debug, Clone)]
-struct Player {
-    marker: Cell, // X or O
+/// How the GUI was started: `[--board WIDTHxHEIGHT[:K]] [--episodes N]`.
+struct GuiOptions {
+    board: BoardSize,
+    /// Episodes the agent trains for before it can be played against.
+    episodes: usize,
 }
 
-impl Player {
-    fn new(marker: Cell) -> Self {
-        Player { marker }
+impl GuiOptions {
+    fn parse(args: &[String]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut board = BoardSize::CLASSIC;
+        let mut episodes = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+            match arg.as_str() {
+                "--board" => board = BoardSize::parse(value()?)?,
+                "--episodes" => episodes = Some(value()?.parse()?),
+                _ => return Err(format!("unknown argument '{}'", arg).into()),
+            }
+        }
+        // larger boards have far more states, so their Q-tables would outgrow memory long
+        // before `TRAIN_EPISODE` episodes; train proportionally fewer by default
+        let episodes = episodes.unwrap_or(TRAIN_EPISODE * 9 / board.cells().max(9));
+        Ok(GuiOptions { board, episodes })
     }
-    fn opponent(&self) -> Self {
-        if self.marker == Cell::X {
-            Player::new(Cell::O)
-        } else if self.marker == Cell::O {
-            Player::new(Cell::X)
-        } else { Player::new(Cell::Empty) }
+}
+
+/// The side of a grid cell and the space between cells, shrinking the cells of larger boards
+/// down to `MIN_CELL`.
+fn cell_layout(size: BoardSize) -> (f32, f32) {
+    let spacing = if size.width.max(size.height) > 5 { 2.0 } else { 5.0 };
+    let rows = size.height as f32;
+    let side = ((GRID_HEIGHT - spacing * (rows - 1.0)) / rows).clamp(MIN_CELL, MAX_CELL);
+    (side, spacing)
+}
+
+/// The height of the grid of a board of `size`.
+fn grid_height(size: BoardSize) -> f32 {
+    let (side, spacing) = cell_layout(size);
+    let rows = size.height as f32;
+    side * rows + spacing * (rows - 1.0)
+}
+
+/// The height of the four Qubic layers stacked above each other, with cell gaps of 2 and a
+/// gap of 10 between the layers. Its cells stay at `MIN_CELL`, so it is taller than the others.
+fn qubic_grid_height() -> f32 {
+    let side = qubic::SIDE as f32;
+    4.0 * (MIN_CELL * side + 2.0 * (side - 1.0)) + 3.0 * 10.0
+}
+
+/// The window, grown to fit grids taller than `GRID_HEIGHT`.
+fn window_size(grid_height: f32) -> iced::Size {
+    iced::Size::new(900.0, WINDOW_HEIGHT + (grid_height - GRID_HEIGHT).max(0.0))
+}
+
+#[derive(Debug, Clone,PartialEq)]
+enum GameMode {
+    PvP,
+    PvA,
+    /// Hosting a networked game, playing `net::HOST_SIDE`.
+    Host,
+    /// Joined a networked game, playing `net::GUEST_SIDE`.
+    Guest,
+}
+
+/// The game played on the board.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Variant {
+    /// k in a row on the board given with `--board`, 3×3 tic-tac-toe by default.
+    Classic,
+    Ultimate,
+    /// Four in a row on a 4×4×4 cube.
+    Qubic,
+    /// The classic board under the rule variant picked in the mode row.
+    Rules,
+}
+
+impl Variant {
+    const ALL: [Variant; 3] = [Variant::Classic, Variant::Ultimate, Variant::Qubic];
+
+    fn label(self) -> &'static str {
+        match self {
+            Variant::Classic => "Classic",
+            Variant::Ultimate => "Ultimate",
+            Variant::Qubic => "Qubic",
+            Variant::Rules => "Classic",
+        }
     }
 }
 
 #[derive(Debug, Clone)]
-struct MoveStatus {
-    move_successful: bool,
-    game_over: bool,
+enum Message {
+    CellClicked(usize, usize),
+    ResetGame,
+    AIMove,
+    Tick,
+    SetGameMode(GameMode),
+    SetVariant(Variant),
+    SetRules(Rules),
+    /// Picks what the next click places, where the rules give a choice.
+    SelectPiece(Piece),
+    OverlayPathChanged(String),
+    LoadOverlay,
+    ClearOverlays,
+    NetworkAddressChanged(String),
+    HostGame,
+    JoinGame,
 }
 
-#[derive(Debug, Clone)]
-struct Board {
-    grid: [[Cell; 3]; 3],
-    players: [Player; 2],
-    current_player: usize,
+/// Sent from the background training thread to the GUI.
+enum TrainingUpdate {
+    Metrics(MetricsRow),
+    Finished(Box<QLearningAgent>),
 }
 
-impl fmt::Display for Cell {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match *self {
-            Cell::Empty => write!(f, "-"),
-            Cell::X => write!(f, "X"),
-            Cell::O => write!(f, "O"),
+struct TicTacToeApp {
+    board: Board,
+    game_over: bool,
+    winner: Option<Cell>,
+    /// The agents trained so far, one for each of the rules.
+    agents: Vec<QLearningAgent>,
+    game_mode: GameMode,
+    variant: Variant,
+    /// `Rules::Standard` unless the variant is `Variant::Rules`.
+    rules: Rules,
+    rules_game: RulesGame,
+    piece: Option<Piece>,
+    ultimate: UltimateBoard,
+    /// Plays O in PvA games of ultimate tic-tac-toe.
+    ultimate_ai: UltimateOpponent,
+    qubic: QubicBoard,
+    qubic_ai: QubicOpponent,
+    ai_thinking: bool,
+    ai_turn_start: Option<Instant>,
+    /// Updates from the training run, until it has finished.
+    training: Option<Receiver<TrainingUpdate>>,
+    /// The rules of the training run, or of the last one.
+    training_rules: Rules,
+    metrics: Vec<MetricsRow>,
+    /// Learning curves of previous runs, loaded from saved metrics logs.
+    overlays: Vec<(String, Vec<MetricsRow>)>,
+    overlay_path: String,
+    overlay_error: Option<String>,
+    /// Episodes of the training run.
+    episodes: usize,
+    /// The peer of a networked game.
+    connection: Option<Connection>,
+    network_address: String,
+    network_status: Option<String>,
+}
+
+impl TicTacToeApp {
+    /// Empties the board of the variant being played.
+    fn reset_board(&mut self) {
+        match self.variant {
+            Variant::Classic => self.board.reset(),
+            Variant::Ultimate => self.ultimate.reset(),
+            Variant::Qubic => self.qubic.reset(),
+            Variant::Rules => self.rules_game.reset(),
         }
     }
-}
 
-impl MoveStatus {
-    fn new(move_successful: bool, game_over: bool) -> Self {
-        MoveStatus {
-            move_successful,
-            game_over
+    fn current_marker(&self) -> Cell {
+        match self.variant {
+            Variant::Classic => self.board.get_current_player().marker,
+            Variant::Ultimate => self.ultimate.get_current_player().marker,
+            Variant::Qubic => self.qubic.get_current_player().marker,
+            Variant::Rules => self.rules_game.get_current_player().marker,
         }
     }
-}
 
-impl Board {
-    fn new() -> Self {
-        Board {
-            grid: [[Cell::Empty; 3]; 3],
-            players: [Player { marker: Cell::X }, Player { marker: Cell::O }],
-            current_player: rand::rng().random_range(0..=1),
+    /// Whether the computer can play the game being shown; the Q-learner needs an agent
+    /// trained for the rules.
+    fn ai_ready(&self) -> bool {
+        match self.variant {
+            Variant::Classic | Variant::Rules => self.agents.iter().any(|agent| agent.metadata().rules == self.rules),
+            Variant::Ultimate | Variant::Qubic => true,
         }
     }
-    fn get_current_player(&self) -> &Player {
-        &self.players[self.current_player]
+
+    /// The piece a click places: the one picked, if it is still the current player's to place.
+    fn selected_piece(&self) -> Piece {
+        let pieces = self.rules_game.pieces();
+        self.piece.filter(|piece| pieces.contains(piece)).unwrap_or(pieces[0])
     }
-    fn make_move(&mut self, row: usize, col: usize) -> (MoveStatus, Option<Cell>) {
-        if self.grid[row][col] == Cell::Empty {
-            let current_player = &self.players[self.current_player];
-            self.grid[row][col] = current_player.marker;
-            self.switch_turn();
-            let (game_over, winner) = self.is_game_over();
-            (MoveStatus::new(true, game_over), winner)
-        } else {
-            (MoveStatus::new(false, false), None)
+
+    /// Starts training an agent for the rules being played if there is none yet. Only one
+    /// run trains at a time, so this is tried again once the current one has finished.
+    fn train_missing_agent(&mut self) {
+        if self.training.is_none() && !self.ai_ready() && self.variant == Variant::Rules {
+            self.metrics.clear();
+            self.training_rules = self.rules;
+            self.training = Some(spawn_training(self.rules_game.size(), self.rules, self.episodes, None));
         }
     }
-    fn switch_turn(&mut self) {
-        self.current_player = 1 - self.current_player;
-    }
-    fn check_winner(&self) -> Option<Cell> {
-        for i in 0..3 {
-            if self.grid[i][0] != Cell::Empty
-                && self.grid[i][1] == self.grid[i][0]
-                && self.grid[i][2] == self.grid[i][1]
-            {
-                return Some(self.grid[i][0]);
-            }
-            if self.grid[0][i] != Cell::Empty
-                && self.grid[1][i] == self.grid[0][i]
-                && self.grid[2][i] == self.grid[1][i]
-            {
-                return Some(self.grid[0][i]);
-            }
+
+    fn switch_game(&mut self, variant: Variant, rules: Rules) -> Command<Message> {
+        self.variant = variant;
+        if rules != self.rules {
+            self.rules = rules;
+            // numerical tic-tac-toe needs the 3×3 board whatever the others are played on
+            let size = self.board.size();
+            self.rules_game = RulesGame::new(rules, if rules.check_board(size).is_ok() { size } else { BoardSize::CLASSIC });
+            self.piece = None;
         }
-        if self.grid[0][0] != Cell::Empty
-            && self.grid[1][1] == self.grid[0][0]
-            && self.grid[2][2] == self.grid[1][1]
+        self.train_missing_agent();
+        // networked games are classic only
+        let mode = match self.game_mode {
+            GameMode::Host | GameMode::Guest => GameMode::PvP,
+            GameMode::PvA if !self.ai_ready() => GameMode::PvP,
+            ref mode => mode.clone(),
+        };
+        let grid_height = match self.variant {
+            Variant::Classic => grid_height(self.board.size()),
+            Variant::Ultimate => GRID_HEIGHT,
+            Variant::Qubic => qubic_grid_height(),
+            Variant::Rules => grid_height(self.rules_game.size()),
+        };
+        let resize = iced::window::resize(iced::window::Id::MAIN, window_size(grid_height));
+        Command::batch([resize, self.update(Message::SetGameMode(mode))])
+    }
+
+    fn start_game(&mut self) {
+        self.reset_board();
+        self.game_over = false;
+        self.winner = None;
+        self.ai_thinking = false;
+        self.ai_turn_start = None;
+    }
+
+    /// Takes the result from the board and, when hosting, sends the position to the guest.
+    fn sync_game(&mut self) {
+        (self.game_over, self.winner) = self.board.is_game_over();
+        if let Some(connection) = &self.connection
+            && self.game_mode == GameMode::Host
         {
-            return Some(self.grid[0][0]);
+            connection.send(&net::state_message(&self.board));
         }
-        if self.grid[0][2] != Cell::Empty
-            && self.grid[1][1] == self.grid[0][2]
-            && self.grid[2][0] == self.grid[1][1]
-        {
-            return Some(self.grid[0][2]);
-        }
-        None
-    }
-
-    fn is_draw(&self) -> bool {
-        self.grid.iter().flatten().all(|&x| x != Cell::Empty)
-    }
-
-    fn is_game_over(&self) -> (bool, Option<Cell>) {
-        let winner = self.check_winner();
-        (winner.is_some() || self.is_draw(), winner)
-    }
-
-    fn board_state(&self) -> String {
-        self.grid
-            .iter()
-            .flatten()
-            .map(|&x| x.to_string())
-            .collect::<Vec<String>>()
-            .join("")
-    }
-
-    fn available_moves(&self) -> Vec<(usize, usize)> {
-        let possible_moves = self
-            .grid
-            .iter()
-            .flatten()
-            .map(|&x| match x {
-                Cell::Empty => 1,
-                _ => 0,
-            })
-            .collect::<Vec<usize>>();
-
-        let total_moves = vec![
-            (0, 0),
-            (0, 1),
-            (0, 2),
-            (1, 0),
-            (1, 1),
-            (1, 2),
-            (2, 0),
-            (2, 1),
-            (2, 2),
-        ];
-        total_moves
-            .into_iter()
-            .zip(possible_moves.into_iter())
-            .filter(|&(_, flag)| flag==1)
-            .map(|(x, _)| x)
-            .collect()
-    }
-    fn find_blocking_move(&self) -> Option<(usize, usize)> {
-        for pos in self.available_moves() {
-            let mut temp_board = self.clone();
-            temp_board.switch_turn();
-            let current_player_marker = temp_board.get_current_player().marker;
-            let (_, winner) = temp_board.make_move(pos.0,pos.1);
-
-            if winner.is_some(){
-                if winner.unwrap() == current_player_marker {
-                    return Some(pos);
+    }
+
+    fn poll_network(&mut self) {
+        let Some(connection) = &self.connection else { return };
+        for event in connection.poll() {
+            match (event, &self.game_mode) {
+                (NetEvent::Connected(peer), GameMode::Host) => {
+                    self.network_status = Some(format!("Playing against {}", peer));
+                    self.sync_game();
+                }
+                (NetEvent::Connected(peer), _) => self.network_status = Some(format!("Connected to {}", peer)),
+                (NetEvent::Disconnected, GameMode::Host) => {
+                    self.network_status = Some("The guest left; waiting for them to reconnect".to_string())
+                }
+                (NetEvent::Disconnected, _) => self.network_status = Some("Connection lost; reconnecting".to_string()),
+                (NetEvent::Failed(err), _) => self.network_status = Some(err),
+                (NetEvent::Received(NetMessage::Move { row, col }), GameMode::Host) => {
+                    match net::play_move(&mut self.board, net::GUEST_SIDE, row, col) {
+                        Ok(()) => self.sync_game(),
+                        Err(message) => {
+                            if let Some(connection) = &self.connection {
+                                connection.send(&NetMessage::Error { message });
+                            }
+                        }
+                    }
+                }
+                (NetEvent::Received(NetMessage::NewGame), GameMode::Host) => {
+                    self.start_game();
+                    self.sync_game();
+                }
+                (NetEvent::Received(NetMessage::State { board, to_move, .. }), GameMode::Guest) => {
+                    match net::board_from_state(self.board.size(), &board, &to_move) {
+                        Some(board) => {
+                            self.board = board;
+                            (self.game_over, self.winner) = self.board.is_game_over();
+                        }
+                        None => {
+                            self.network_status =
+                                Some(format!("The host's board is not {}; start both with the same --board", self.board.size()))
+                        }
+                    }
+                }
+                (NetEvent::Received(NetMessage::Error { message }), _) => self.network_status = Some(message),
+                (NetEvent::Received(message), _) => {
+                    self.network_status = Some(format!("Unexpected message from the peer: {:?}", message))
                 }
             }
         }
-        None
-    }
-    fn reset(&mut self) {
-        let mut rng = rand::rng();
-        self.grid = [[Cell::Empty; 3]; 3];
-        self.current_player = rng.random_range(0..=1);
     }
-}
 
-impl fmt::Display for Board {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "{}", self.board_state())
+    /// Whether the player at this window may move now.
+    fn my_turn(&self) -> bool {
+        let marker = self.current_marker();
+        match self.game_mode {
+            GameMode::PvP => true,
+            GameMode::PvA => !self.ai_thinking,
+            GameMode::Host => marker == net::HOST_SIDE,
+            GameMode::Guest => marker == net::GUEST_SIDE && self.connection.as_ref().is_some_and(Connection::is_connected),
+        }
     }
 }
 
-#[derive(Debug,Serialize,Deserialize)]
-struct QLearningAgent {
-    q_table: HashMap<String, HashMap<String, f64>>,
-    alpha: f64,
-    gamma: f64,
-    epsilon: f64,
-    train: bool
-}
+impl TicTacToeApp {
+    fn classic_grid(&self) -> Column<'_, Message> {
+        let size = self.board.size();
+        let (cell_side, cell_spacing) = cell_layout(size);
+        let mut grid = Column::new().spacing(cell_spacing).width(Length::Fill);
+        for i in 0..size.height {
+            let mut row_widgets = row!().spacing(cell_spacing).width(Length::Fill);
+            for j in 0..size.width {
+                let cell = self.board.cell(i, j);
+                let playable = cell == Cell::Empty && !self.game_over && self.my_turn();
+                row_widgets = row_widgets.push(cell_button(cell, cell_side, playable.then_some(Message::CellClicked(i, j))));
+            }
+            grid = grid.push(row_widgets);
+        }
+        grid
+    }
 
-impl QLearningAgent {
-    fn new(alpha: f64, gamma: f64, epsilon: f64) -> Self {
-        QLearningAgent {
-            q_table: HashMap::new(),
-            alpha,
-            gamma,
-            epsilon,
-            train: true
+    /// The nine local boards. The ones the next move may be played on are highlighted, and
+    /// won ones are covered by the winner's mark.
+    fn ultimate_grid(&self) -> Column<'_, Message> {
+        // three rows of local boards with two cell gaps each, and the gaps between the boards
+        let side = (GRID_HEIGHT - 40.0) / 9.0;
+        let mut grid = Column::new().spacing(8).width(Length::Fill);
+        for meta_row in 0..3 {
+            let mut row_widgets = row!().spacing(8).width(Length::Fill);
+            for meta_col in 0..3 {
+                let board = meta_row * 3 + meta_col;
+                let local: Element<'_, Message> = match self.ultimate.local_winner(board) {
+                    Some(winner) => cell_button(winner, 3.0 * side + 4.0, None).into(),
+                    None => {
+                        let mut local = Column::new().spacing(2);
+                        for row in meta_row * 3..meta_row * 3 + 3 {
+                            let mut cells = row!().spacing(2).width(Length::Fill);
+                            for col in meta_col * 3..meta_col * 3 + 3 {
+                                let cell = self.ultimate.cell(row, col);
+                                let playable = cell == Cell::Empty && self.ultimate.is_playable(board) && self.my_turn();
+                                cells = cells.push(cell_button(cell, side, playable.then_some(Message::CellClicked(row, col))));
+                            }
+                            local = local.push(cells);
+                        }
+                        local.into()
+                    }
+                };
+                let local = container(local).padding(2).width(Length::Fill);
+                row_widgets = row_widgets.push(if self.ultimate.is_playable(board) {
+                    local.style(iced::theme::Container::Box)
+                } else {
+                    local
+                });
+            }
+            grid = grid.push(row_widgets);
         }
+        grid
     }
-    fn get_q_value(&mut self, state: &str, action: &str) -> f64 {
-        *self
-            .q_table
-            .entry(state.to_string())
-            .or_insert(HashMap::new())
-            .entry(action.to_string())
-            .or_insert(0.0)
+
+    /// The boards of a rule variant side by side; dead boards of Notakto cannot be played on.
+    /// A cell is `CellClicked(row, board * width + col)`.
+    fn rules_grid(&self) -> Column<'_, Message> {
+        let size = self.rules_game.size();
+        let (cell_side, cell_spacing) = cell_layout(size);
+        let mut boards = Row::new().spacing(20).width(Length::Fill);
+        for board in 0..self.rules_game.boards() {
+            let live = self.rules_game.is_live(board);
+            let mut grid = Column::new().spacing(cell_spacing).width(Length::Fill);
+            for row in 0..size.height {
+                let mut row_widgets = row!().spacing(cell_spacing).width(Length::Fill);
+                for col in 0..size.width {
+                    let piece = self.rules_game.piece(board, row, col);
+                    let playable = piece.is_none() && live && !self.game_over && self.my_turn();
+                    row_widgets = row_widgets.push(piece_button(
+                        piece,
+                        self.rules_game.cell(board, row, col),
+                        cell_side,
+                        playable.then_some(Message::CellClicked(row, board * size.width + col)),
+                    ));
+                }
+                grid = grid.push(row_widgets);
+            }
+            boards = boards.push(grid);
+        }
+        Column::new().push(boards)
     }
 
-    fn update_q_value(&mut self, state: &str, action: &str, reward: f64, next_state: &str) {
-        let max_q_next = self
-            .q_table
-            .get(next_state)
-            .map(|actions| actions.values().cloned().fold(f64::NEG_INFINITY, f64::max))
-            .unwrap_or(0.0);
-        let old_q_value = self.get_q_value(state, action);
-        let new_q_value =
-            old_q_value + self.alpha * (reward + self.gamma * max_q_next - old_q_value);
-        self.q_table
-            .entry(state.to_string())
-            .or_insert(HashMap::new())
-            .insert(action.to_string(), new_q_value);
+    /// Buttons picking the piece to place, for rules that give the current player a choice.
+    fn piece_picker(&self) -> Option<Row<'_, Message>> {
+        let pieces = self.rules_game.pieces();
+        if self.variant != Variant::Rules || pieces.len() < 2 || self.game_over {
+            return None;
+        }
+        let selected = self.selected_piece();
+        Some(pieces.into_iter().fold(Row::new().spacing(5), |picker, piece| {
+            picker.push(
+                button(text(piece.to_string()))
+                    .on_press(Message::SelectPiece(piece))
+                    .style(if piece == selected {
+                        iced::theme::Button::Primary
+                    } else {
+                        iced::theme::Button::Secondary
+                    }),
+            )
+        }))
     }
 
-    fn choose_action(&mut self, state: &str, available_moves: &Vec<(usize, usize)>, blocking_move: Option<(usize, usize)>) -> ((usize, usize),bool,bool) {
-        if blocking_move.is_some() && self.train {
-            (blocking_move.unwrap(), true, false)
-        } else {
-        let mut rng = rand::rng();
-        if (rng.random::<f64>() < self.epsilon) && self.train {
-            (*available_moves.choose(&mut rng).unwrap(),false, true)
-        } else {
-            let q_values = self.q_table.get_mut(state);
-            if let Some(actions) = q_values {
-                let best_action = available_moves
-                    .iter()
-                    .max_by(|&a, &b| {
-                        let str_a = format!("{},{}", a.0,a.1);
-                        let str_b = format!("{},{}", b.0,b.1);
-                        let q_a = actions.get(&str_a).unwrap_or(&0.0);
-                        let q_b = actions.get(&str_b).unwrap_or(&0.0);
-                        q_a.partial_cmp(&q_b).unwrap()
-                    }).unwrap();
-                (*best_action, false, false)
-            } else {
-                (*available_moves.choose(&mut rng).unwrap(),false, false)
-            }
-        }
-        }
-    }
-    fn save_to_file(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let json = serde_json::to_string(&self)?;
-        fs::write(FILENAME, json)?;
-        Ok(())
-    }
-
-}
-
-fn train_q_learning(agent: &mut QLearningAgent, episodes: usize) {
-    let mut exploration: i64 = 0;
-    let mut exploitation: i64 = 0;
-    let mut total_loop: i64 = 0;
-    let min_epsilon: f64 = 0.1;
-    let epsilon_start: f64 = agent.epsilon;
-    for episode in 0..episodes {
-        let mut game = Board::new();
-        let mut state_history: Vec<String> = Vec::new();
-        let mut action_history: Vec<String> = Vec::new();
-        loop {
-            let current_player = game.get_current_player().clone();
-            let prev_player = current_player.opponent();
-            let (game_over,winner) = game.is_game_over();
-            if game_over {
-                // if we win the game we propagate reward to the previous state space
-                assert!(state_history.len() > 2 , "state history is less than three which is a undesired condition");
-                action_history.pop();
-                if winner.unwrap_or(Cell::Empty) == prev_player.marker {
-                    let mut propagation_reward = 0.7;
-                    let decay_percentage = 0.7;
-                    let action_val = action_history.pop().unwrap();
-                    //assert!(action.is_some());
-                    for states in state_history.windows(2) {
-                        agent.update_q_value(&states[0],&action_val,propagation_reward,&states[1]);
-                        propagation_reward = (agent.alpha * decay_percentage).min(0.1);
-                    }
+    /// The four layers of the cube stacked from the top one down, as `qubic_grid_height`
+    /// lays them out. Rows of the grid run down through the layers, so a cell is
+    /// `CellClicked(layer * SIDE + row, col)`.
+    fn qubic_grid(&self) -> Column<'_, Message> {
+        const SIDE: usize = qubic::SIDE;
+        let mut grid = Column::new().spacing(10).width(Length::Fill);
+        for layer in 0..SIDE {
+            let mut cells = Column::new().spacing(2).width(Length::Fill);
+            for row in 0..SIDE {
+                let mut row_cells = row!().spacing(2).width(Length::Fill);
+                for col in 0..SIDE {
+                    let cell = self.qubic.cell(layer, row, col);
+                    let playable = cell == Cell::Empty && !self.game_over && self.my_turn();
+                    row_cells = row_cells.push(cell_button(
+                        cell,
+                        MIN_CELL,
+                        playable.then_some(Message::CellClicked(layer * SIDE + row, col)),
+                    ));
                 }
-                break;
-            };
-            let state = game.board_state();
-            state_history.push(state.clone());
-            let moves = game.available_moves();
-            let blocking_move = game.find_blocking_move();
-            let (action,is_blocking_move,explore) = agent.choose_action(&state, &moves, blocking_move);
-            if explore {exploration += 1} else {exploitation += 1;}
-            total_loop += 1;
-            let action_hash = format!("{},{}", action.0, action.1);
-            game.make_move(action.0,action.1);
-            action_history.push(action_hash.clone());
-            let empty_cells = state.chars().into_iter().filter(|&c| c == '-').count();
-            let mut blocking_reward = 0.4;
-            if empty_cells > 5 {
-                blocking_reward = 0.9;
-            }
-            let reward = if game.check_winner().unwrap_or(Cell::Empty) == current_player.marker { 1.0 }
-                                else if is_blocking_move {blocking_reward}
-                                else if game.is_draw(){0.3}
-                                else {0.0};
-            let next_state = game.board_state();
-            agent.update_q_value(&state,&action_hash,reward,&next_state);
-        }
-        agent.epsilon = (epsilon_start - episode as f64 * (epsilon_start - min_epsilon)/TRAIN_EPISODE as f64).max(min_epsilon);
-    }
-    if agent.save_to_file().is_ok() {
-        println!("Saved game data to {}", FILENAME);
-    };
-    println!("Exploration: {:.2}, Exploitation: {:.2}", (exploration as f64)/(total_loop as f64), (exploitation as f64)/(total_loop as f64));
+                cells = cells.push(row_cells);
+            }
+            grid = grid.push(cells);
+        }
+        grid
+    }
 }
 
-impl Default for Board {
-    fn default() -> Self {
-        Self::new()
-    }
+/// A grid cell showing its mark; it can only be pressed with an `on_press` message.
+fn cell_button<'a>(cell: Cell, side: f32, on_press: Option<Message>) -> iced::widget::Button<'a, Message> {
+    let cell_text = match cell {
+        Cell::X => "X",
+        Cell::O => "O",
+        Cell::Empty => " ",
+    };
+    let style = match cell {
+        Cell::X => iced::theme::Button::Positive,
+        Cell::O => iced::theme::Button::Destructive,
+        Cell::Empty => iced::theme::Button::Secondary,
+    };
+    grid_button(cell_text, style, side, on_press)
 }
 
-#[derive(Debug, Clone,PartialEq)]
-enum GameMode {
-    PvP,
-    PvA
+/// A cell of a rule variant, where `marker` is the one on the board. Numbers take the colour
+/// of the player who placed them.
+fn piece_button<'a>(piece: Option<Piece>, marker: Cell, side: f32, on_press: Option<Message>) -> iced::widget::Button<'a, Message> {
+    match piece {
+        Some(Piece::Number(number)) => {
+            let style = if marker == Cell::X { iced::theme::Button::Positive } else { iced::theme::Button::Destructive };
+            grid_button(&number.to_string(), style, side, on_press)
+        }
+        _ => cell_button(marker, side, on_press),
+    }
 }
 
-#[derive(Debug, Clone)]
-enum Message {
-    CellClicked(usize, usize),
-    ResetGame,
-    AIMove,
-    Tick,
-    SetGameMode(GameMode)
+fn grid_button<'a>(label: &str, style: iced::theme::Button, side: f32, on_press: Option<Message>) -> iced::widget::Button<'a, Message> {
+    button(
+        text(label)
+            .size(side / 2.0)
+            .horizontal_alignment(alignment::Horizontal::Center)
+            .vertical_alignment(alignment::Vertical::Center),
+    )
+        .width(Length::Fill)
+        .height(Length::Fixed(side))
+        .style(style)
+        .on_press_maybe(on_press)
 }
 
-struct TicTacToeApp {
-    board: Board,
-    game_over: bool,
-    winner: Option<Cell>,
-    ai_agent: QLearningAgent,
-    game_mode: GameMode,
-    ai_thinking: bool,
-    ai_turn_start: Option<Instant>,
+/// Trains an agent in the background, sending its learning curves and then the agent itself.
+fn spawn_training(board: BoardSize, rules: Rules, episodes: usize, output: Option<String>) -> Receiver<TrainingUpdate> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut agent: QLearningAgent = QLearningAgent::new(0.08,0.7,0.9);
+        let mut options = TrainingOptions::new(episodes);
+        options.board = board;
+        options.rules = rules;
+        options.output = output;
+        let progress = sender.clone();
+        options.metrics = Some(MetricsLog::callback(CHART_INTERVAL, move |row| {
+            let _ = progress.send(TrainingUpdate::Metrics(row));
+        }));
+        train_q_learning(&mut agent, &mut options);
+        agent.set_training(false);
+        let _ = sender.send(TrainingUpdate::Finished(Box::new(agent)));
+    });
+    receiver
 }
 
 impl Application for TicTacToeApp {
     type Executor = executor::Default;
     type Message = Message;
     type Theme = Theme;
-    type Flags = ();
+    type Flags = GuiOptions;
 
-    fn new(_flags: ()) -> (Self, Command<Message>) {
-        let mut agent: QLearningAgent = QLearningAgent::new(0.08,0.7,0.9);
-        train_q_learning(&mut agent,TRAIN_EPISODE);
-        agent.train = false;
+    fn new(flags: GuiOptions) -> (Self, Command<Message>) {
+        let receiver = spawn_training(flags.board, Rules::Standard, flags.episodes, Some(FILENAME.to_string()));
         (
             TicTacToeApp {
-                board: Board::new(),
+                board: Board::with_size(flags.board),
                 game_over: false,
                 winner: None,
-                ai_agent: agent,
+                agents: Vec::new(),
                 game_mode: GameMode::PvP,
+                variant: Variant::Classic,
+                rules: Rules::Standard,
+                rules_game: RulesGame::new(Rules::Standard, flags.board),
+                piece: None,
+                ultimate: UltimateBoard::new(),
+                ultimate_ai: UltimateOpponent::Search { depth: UltimateOpponent::DEFAULT_DEPTH },
+                qubic: QubicBoard::new(),
+                qubic_ai: QubicOpponent::Heuristic,
                 ai_thinking: false,
                 ai_turn_start: None,
+                training: Some(receiver),
+                training_rules: Rules::Standard,
+                metrics: Vec::new(),
+                overlays: Vec::new(),
+                overlay_path: String::new(),
+                overlay_error: None,
+                episodes: flags.episodes,
+                connection: None,
+                network_address: net::DEFAULT_ADDRESS.to_string(),
+                network_status: None,
             },
             Command::none(),
         )
@@ -408,16 +583,37 @@ impl Application for TicTacToeApp {
 
     fn update(&mut self, message: Message) -> Command<Message> {
         match message {
+            Message::CellClicked(row, col) if self.game_mode == GameMode::Host => {
+                if net::play_move(&mut self.board, net::HOST_SIDE, row, col).is_ok() {
+                    self.sync_game();
+                }
+            }
+            Message::CellClicked(row, col) if self.game_mode == GameMode::Guest => {
+                if let Some(connection) = &self.connection {
+                    connection.send(&NetMessage::Move { row, col });
+                }
+            }
             Message::CellClicked(row, col) => {
                 if !self.game_over && !self.ai_thinking {
-                    let (move_status, winner) = self.board.make_move(row, col);
+                    let (move_status, winner) = match self.variant {
+                        Variant::Classic => self.board.make_move(row, col),
+                        Variant::Ultimate => self.ultimate.make_move(row, col),
+                        // the layers are stacked in the grid's rows
+                        Variant::Qubic => self.qubic.make_move(row / qubic::SIDE, row % qubic::SIDE, col),
+                        // the boards of Notakto stand side by side
+                        Variant::Rules => {
+                            let width = self.rules_game.size().width;
+                            let piece = self.selected_piece();
+                            self.rules_game.make_move(RulesMove { board: col / width, row, col: col % width, piece })
+                        }
+                    };
                     if move_status.move_successful {
                         self.game_over = move_status.game_over;
                         self.winner = winner;
 
                         if !self.game_over
                             && self.game_mode == GameMode::PvA
-                            && self.board.get_current_player().marker == Cell::O
+                            && self.current_marker() == Cell::O
                         {
                             self.ai_thinking = true;
                             self.ai_turn_start = Some(Instant::now());
@@ -429,15 +625,24 @@ impl Application for TicTacToeApp {
                     }
                 }
             }
+            Message::ResetGame if self.game_mode == GameMode::Host => {
+                self.start_game();
+                self.sync_game();
+            }
+            Message::ResetGame if self.game_mode == GameMode::Guest => {
+                if let Some(connection) = &self.connection {
+                    connection.send(&NetMessage::NewGame);
+                }
+            }
             Message::ResetGame => {
-                self.board.reset();
+                self.reset_board();
                 self.game_over = false;
                 self.winner = None;
                 self.ai_thinking = false;
                 self.ai_turn_start = None;
 
                 if self.game_mode == GameMode::PvA
-                    && self.board.get_current_player().marker == Cell::O {
+                    && self.current_marker() == Cell::O {
                     self.ai_thinking = true;
                     self.ai_turn_start = Some(Instant::now());
                     return Command::perform(
@@ -446,12 +651,43 @@ impl Application for TicTacToeApp {
                     )
                 }
             }
+            Message::AIMove if self.variant == Variant::Ultimate => {
+                if !self.ultimate.is_game_over().0 {
+                    let (row, col) = self.ultimate_ai.choose_move(&self.ultimate);
+                    let (move_status, winner) = self.ultimate.make_move(row, col);
+                    self.game_over = move_status.game_over;
+                    self.winner = winner;
+                }
+                self.ai_thinking = false;
+            }
+            Message::AIMove if self.variant == Variant::Qubic => {
+                if !self.qubic.is_game_over().0 {
+                    let (layer, row, col) = self.qubic_ai.choose_move(&self.qubic);
+                    let (move_status, winner) = self.qubic.make_move(layer, row, col);
+                    self.game_over = move_status.game_over;
+                    self.winner = winner;
+                }
+                self.ai_thinking = false;
+            }
+            Message::AIMove if self.variant == Variant::Rules => {
+                if let Some(agent) = self.agents.iter().find(|agent| agent.metadata().rules == self.rules)
+                    && !self.rules_game.is_game_over().0
+                {
+                    let (action, _) = rules::choose_move(agent, &self.rules_game);
+                    let (move_status, winner) = self.rules_game.make_move(action);
+                    self.game_over = move_status.game_over;
+                    self.winner = winner;
+                }
+                self.ai_thinking = false;
+            }
             Message::AIMove => {
                 let available_moves = self.board.available_moves();
                 let blocking_move = self.board.find_blocking_move();
-                if !available_moves.is_empty() {
+                if let Some(agent) = self.agents.iter_mut().find(|agent| agent.metadata().rules == Rules::Standard)
+                    && !available_moves.is_empty()
+                {
                     let state = self.board.board_state();
-                    let (action,_,_) = self.ai_agent.choose_action(&state, &available_moves,blocking_move);
+                    let (action,_,_) = agent.choose_action(&state, &available_moves,blocking_move);
                     let (move_status, winner) = self.board.make_move(action.0,action.1);
                     self.game_over = move_status.game_over;
                     self.winner = winner;
@@ -459,37 +695,109 @@ impl Application for TicTacToeApp {
                 self.ai_thinking = false;
             }
             Message::Tick => {
-                if self.ai_thinking {
-                    if let Some(start_time) = self.ai_turn_start {
-                        if start_time.elapsed() >= Duration::from_millis(500) {
-                            return Command::perform(
-                                async {},
-                                |_| Message::AIMove,
-                            )
+                if let Some(receiver) = &self.training {
+                    let mut finished = false;
+                    for update in receiver.try_iter() {
+                        match update {
+                            TrainingUpdate::Metrics(row) => self.metrics.push(row),
+                            TrainingUpdate::Finished(agent) => {
+                                self.agents.push(*agent);
+                                finished = true;
+                            }
                         }
                     }
+                    if finished {
+                        self.training = None;
+                        self.train_missing_agent();
+                    }
+                }
+                self.poll_network();
+                if self.ai_thinking
+                    && let Some(start_time) = self.ai_turn_start
+                    && start_time.elapsed() >= Duration::from_millis(500)
+                {
+                    return Command::perform(
+                        async {},
+                        |_| Message::AIMove,
+                    )
                 }
             }
             Message::SetGameMode(mode) => {
+                self.connection = None;
+                self.network_status = None;
                 self.game_mode = mode;
-                self.board.reset();
+                self.reset_board();
                 self.game_over = false;
                 self.winner = None;
                 self.ai_thinking = false;
                 self.ai_turn_start = None;
 
                 if self.game_mode == GameMode::PvA
-                    && self.board.get_current_player().marker == Cell::O {
+                    && self.current_marker() == Cell::O {
                     self.ai_thinking = true;
                     self.ai_turn_start = Some(Instant::now());
                 }
             }
+            Message::SetVariant(variant) => return self.switch_game(variant, Rules::Standard),
+            Message::SetRules(Rules::Standard) => return self.switch_game(Variant::Classic, Rules::Standard),
+            Message::SetRules(rules) => return self.switch_game(Variant::Rules, rules),
+            Message::SelectPiece(piece) => self.piece = Some(piece),
+            Message::OverlayPathChanged(path) => {
+                self.overlay_path = path;
+            }
+            Message::LoadOverlay => {
+                let path = self.overlay_path.trim().to_string();
+                match metrics::read_metrics(&path) {
+                    Ok(rows) => {
+                        self.overlays.push((path, rows));
+                        self.overlay_path.clear();
+                        self.overlay_error = None;
+                    }
+                    Err(err) => self.overlay_error = Some(err.to_string()),
+                }
+            }
+            Message::ClearOverlays => {
+                self.overlays.clear();
+                self.overlay_error = None;
+            }
+            Message::NetworkAddressChanged(address) => {
+                self.network_address = address;
+            }
+            Message::HostGame => {
+                self.connection = None;
+                match Connection::host(self.network_address.trim()) {
+                    Ok(connection) => {
+                        self.connection = Some(connection);
+                        self.game_mode = GameMode::Host;
+                        self.start_game();
+                        self.network_status = Some(format!("Waiting for a guest on {}", self.network_address.trim()));
+                    }
+                    Err(err) => self.network_status = Some(format!("Cannot host on {}: {}", self.network_address.trim(), err)),
+                }
+            }
+            Message::JoinGame => {
+                self.connection = Some(Connection::join(self.network_address.trim()));
+                self.game_mode = GameMode::Guest;
+                self.start_game();
+                self.network_status = Some(format!("Connecting to {}", self.network_address.trim()));
+            }
         }
         Command::none()
     }
 
-    fn view(&self) -> Element<Message> {
-        let title = text("Tic-Tac-Toe")
+    fn view(&self) -> Element<'_, Message> {
+        let size = self.board.size();
+        let title = match self.variant {
+            Variant::Ultimate => "Ultimate Tic-Tac-Toe".to_string(),
+            Variant::Qubic => "Qubic".to_string(),
+            Variant::Rules => match self.rules {
+                rules @ Rules::Notakto { .. } => rules.to_string(),
+                rules => format!("{} Tic-Tac-Toe", rules),
+            },
+            Variant::Classic if size == BoardSize::CLASSIC => "Tic-Tac-Toe".to_string(),
+            Variant::Classic => format!("{} in a row on {}x{}", size.k, size.width, size.height),
+        };
+        let title = text(title)
             .size(40)
             .width(Length::Fill)
             .horizontal_alignment(alignment::Horizontal::Center);
@@ -505,31 +813,70 @@ impl Application for TicTacToeApp {
                     iced::theme::Button::Secondary
                 }),
             button(text("Player vs AI").horizontal_alignment(alignment::Horizontal::Center))
-                .on_press(Message::SetGameMode(GameMode::PvA))
+                .on_press_maybe(self.ai_ready().then_some(Message::SetGameMode(GameMode::PvA)))
                 .width(Length::Fill)
                 .style(if self.game_mode == GameMode::PvA {
                     iced::theme::Button::Primary
                 } else {
                     iced::theme::Button::Secondary
-                })
+                }),
+            pick_list(&Rules::ALL[..], Some(self.rules), Message::SetRules),
         ]
             .spacing(20);
 
+        // Variant selection
+        let variant_row = Variant::ALL.into_iter().fold(Row::new().spacing(10), |variant_row, variant| {
+            variant_row.push(
+                button(text(variant.label()).horizontal_alignment(alignment::Horizontal::Center))
+                    .on_press(Message::SetVariant(variant))
+                    .width(Length::Fill)
+                    .style(if self.variant == variant || (variant == Variant::Classic && self.variant == Variant::Rules) {
+                        iced::theme::Button::Primary
+                    } else {
+                        iced::theme::Button::Secondary
+                    }),
+            )
+        });
+
+        // Networked games of the classic variant: X hosts, O joins
+        let network_row = row![
+            text_input("host:port", &self.network_address)
+                .on_input(Message::NetworkAddressChanged)
+                .width(Length::Fill),
+            button(text("Host"))
+                .on_press_maybe((self.variant == Variant::Classic).then_some(Message::HostGame))
+                .style(if self.game_mode == GameMode::Host {
+                    iced::theme::Button::Primary
+                } else {
+                    iced::theme::Button::Secondary
+                }),
+            button(text("Join"))
+                .on_press_maybe((self.variant == Variant::Classic).then_some(Message::JoinGame))
+                .style(if self.game_mode == GameMode::Guest {
+                    iced::theme::Button::Primary
+                } else {
+                    iced::theme::Button::Secondary
+                }),
+        ]
+            .spacing(10);
+
         // Current player or game result display
         let status_text = if self.game_over {
             match self.winner {
                 Some(Cell::X) => "Player X wins!",
                 Some(Cell::O) => match self.game_mode {
-                    GameMode::PvP => "Player O wins!",
                     GameMode::PvA => "AI wins!",
+                    _ => "Player O wins!",
                 },
                 _ => "It's a draw!",
             }
         } else {
-            match self.board.get_current_player().marker {
+            match self.current_marker() {
+                _ if matches!(self.game_mode, GameMode::Host | GameMode::Guest) => {
+                    if self.my_turn() { "Your turn" } else { "Waiting for your opponent" }
+                }
                 Cell::X => "Player X's turn",
                 Cell::O => match self.game_mode {
-                    GameMode::PvP => "Player O's turn",
                     GameMode::PvA => {
                         if self.ai_thinking {
                             "AI is thinking..."
@@ -537,6 +884,7 @@ impl Application for TicTacToeApp {
                             "AI's turn"
                         }
                     },
+                    _ => "Player O's turn",
                 },
                 _ => "",
             }
@@ -548,43 +896,12 @@ impl Application for TicTacToeApp {
             .horizontal_alignment(alignment::Horizontal::Center);
 
         // Build the game grid
-        let mut grid = Column::new().spacing(5).width(Length::Fill);
-
-        for i in 0..3 {
-            let mut row_widgets = row!().spacing(5).width(Length::Fill);
-
-            for j in 0..3 {
-                let cell_text = match self.board.grid[i][j] {
-                    Cell::X => "X",
-                    Cell::O => "O",
-                    Cell::Empty => " ",
-                };
-
-                let cell_button = button(
-                    text(cell_text)
-                        .size(40)
-                        .horizontal_alignment(alignment::Horizontal::Center)
-                        .vertical_alignment(alignment::Vertical::Center),
-                )
-                    .width(Length::Fill)
-                    .height(Length::Fixed(80.0))
-                    .style(match self.board.grid[i][j] {
-                        Cell::X => iced::theme::Button::Positive,
-                        Cell::O => iced::theme::Button::Destructive,
-                        Cell::Empty => iced::theme::Button::Secondary,
-                    });
-
-                let cell = if self.board.grid[i][j] == Cell::Empty && !self.game_over && !self.ai_thinking {
-                    cell_button.on_press(Message::CellClicked(i, j))
-                } else {
-                    cell_button
-                };
-
-                row_widgets = row_widgets.push(cell);
-            }
-
-            grid = grid.push(row_widgets);
-        }
+        let grid = match self.variant {
+            Variant::Classic => self.classic_grid(),
+            Variant::Ultimate => self.ultimate_grid(),
+            Variant::Qubic => self.qubic_grid(),
+            Variant::Rules => self.rules_grid(),
+        };
 
         // Reset button
         let reset_button = button(text("New Game"))
@@ -597,15 +914,75 @@ impl Application for TicTacToeApp {
         let content = Column::new()
             .push(title)
             .push(game_mode_row)
+            .push(variant_row)
+            .push(network_row)
+            .push_maybe(self.network_status.as_deref().map(|status| text(status).size(14)))
             .push(status)
             .push(grid)
-            .push(Row::new().push(reset_button).width(Length::Fill).padding(10).align_items(alignment::Alignment::Center))
+            .push(
+                Row::new()
+                    .push(reset_button)
+                    .push_maybe(self.piece_picker())
+                    .width(Length::Fill)
+                    .padding(10)
+                    .spacing(20)
+                    .align_items(alignment::Alignment::Center),
+            )
             .padding(20)
             .spacing(20)
             .width(Length::Fill)
             .max_width(500.0);
 
-        container(content)
+        // Learning curves of the training run and any overlaid previous runs
+        let training_status = match &self.training {
+            Some(_) if self.training_rules != Rules::Standard => format!(
+                "Training for {} rules: {}/{} episodes",
+                self.training_rules,
+                self.metrics.last().map_or(0, |row| row.episode),
+                self.episodes
+            ),
+            Some(_) => format!(
+                "Training: {}/{} episodes",
+                self.metrics.last().map_or(0, |row| row.episode),
+                self.episodes
+            ),
+            None => format!("Trained for {} episodes", self.metrics.last().map_or(0, |row| row.episode)),
+        };
+        let mut chart_column = Column::new()
+            .push(text("Learning curves").size(24))
+            .push(text(training_status))
+            .push(
+                canvas(chart::LearningCurves {
+                    current: &self.metrics,
+                    episodes: self.episodes,
+                    overlays: &self.overlays,
+                })
+                .width(Length::Fill)
+                .height(Length::Fixed(300.0)),
+            )
+            .push(
+                row![
+                    text_input("metrics file of a previous run", &self.overlay_path)
+                        .on_input(Message::OverlayPathChanged)
+                        .on_submit(Message::LoadOverlay),
+                    button(text("Overlay")).on_press(Message::LoadOverlay),
+                    button(text("Clear"))
+                        .on_press_maybe((!self.overlays.is_empty()).then_some(Message::ClearOverlays))
+                        .style(iced::theme::Button::Secondary),
+                ]
+                .spacing(5),
+            )
+            .padding(20)
+            .spacing(10)
+            .width(Length::Fill);
+        for (path, _) in &self.overlays {
+            chart_column = chart_column.push(text(format!("Overlay: {}", path)).size(14));
+        }
+        if let Some(err) = &self.overlay_error {
+            chart_column = chart_column.push(text(err).size(14).style(iced::Color::from_rgb(0.85, 0.3, 0.3)));
+        }
+
+        container(row![content, chart_column])
             .width(Length::Fill)
             .height(Length::Fill)
             .center_x()
@@ -620,16 +997,620 @@ impl Application for TicTacToeApp {
 
 
 
+/// A subcommand run instead of the GUI; receives the arguments following its name.
+type CliCommand = fn(&[String]) -> Result<(), Box<dyn std::error::Error>>;
+
+/// `train [--episodes N] [--opponents SPEC] [--curriculum FILE|auto] [--curriculum-rate START:END]
+/// [--curriculum-review N] [--replay CAPACITY] [--replay-ratio R] [--replay-sampling uniform|prioritized[@EXP]]
+/// [--alpha-schedule constant|visits] [--exploration epsilon|ucb[@C]] [--stop-change MAX[:MEAN]] [--stop-window N]
+/// [--eval-opponent KIND] [--eval-interval N] [--eval-games N] [--patience N] [--min-improvement X]
+/// [--metrics FILE.csv|FILE.jsonl] [--metrics-interval N] [--seed N] [--output FILE] [--checkpoint-every N]
+/// [--checkpoint-keep K] [--resume CHECKPOINT] [--board WIDTHxHEIGHT[:K]] [--rules RULES]` trains a fresh
+/// agent and saves it without opening the GUI. A resumed run keeps the checkpoint's hyperparameters,
+/// board and rules, but takes a given alpha schedule or exploration, and replay, curriculum and
+/// early stopping start afresh.
+fn run_training(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut options = TrainingOptions::new(TRAIN_EPISODE);
+    let mut curriculum_rate = None;
+    let mut curriculum_review = None;
+    let mut replay_capacity = None;
+    let mut replay_ratio: f64 = 1.0;
+    let mut replay_sampling = Sampling::Uniform;
+    let mut agent: QLearningAgent = QLearningAgent::new(0.08,0.7,0.9);
+    let mut alpha_schedule = None;
+    let mut exploration = None;
+    let mut early_stopping = EarlyStopping::new(Opponent::parse("minimax")?);
+    let mut use_early_stopping = false;
+    let mut metrics_path = None;
+    let mut metrics_interval = 1000;
+    let mut checkpoint_interval = None;
+    let mut checkpoint_keep = 3;
+    let mut resume = None;
+    let mut board = None;
+    let mut rules = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--episodes" => options.episodes = value()?.parse()?,
+            "--board" => board = Some(BoardSize::parse(value()?)?),
+            "--rules" => rules = Some(Rules::parse(value()?)?),
+            "--opponents" => options.opponents = OpponentPool::parse(value()?)?,
+            "--curriculum" => {
+                options.curriculum = Some(match value()?.as_str() {
+                    "auto" => Curriculum::auto(),
+                    path => Curriculum::from_file(path)?,
+                })
+            }
+            "--curriculum-rate" => {
+                let rate = value()?;
+                let (start, end) = rate.split_once(':').unwrap_or((rate, rate));
+                curriculum_rate = Some((start.parse::<f64>()?, end.parse::<f64>()?));
+            }
+            "--curriculum-review" => curriculum_review = Some(value()?.parse::<usize>()?),
+            "--replay" => replay_capacity = Some(value()?.parse::<usize>()?),
+            "--replay-ratio" => replay_ratio = value()?.parse()?,
+            "--replay-sampling" => replay_sampling = Sampling::parse(value()?)?,
+            "--stop-change" => {
+                let change = value()?;
+                let (max, mean) = change.split_once(':').unwrap_or((change, "inf"));
+                early_stopping.max_change = Some(max.parse()?);
+                early_stopping.mean_change = mean.parse()?;
+                use_early_stopping = true;
+            }
+            "--stop-window" => early_stopping.window = value()?.parse::<usize>()?.max(1),
+            "--eval-opponent" => early_stopping.opponent = Opponent::parse(value()?)?,
+            "--eval-interval" => {
+                early_stopping.evaluation_interval = Some(value()?.parse::<usize>()?.max(1));
+                use_early_stopping = true;
+            }
+            "--eval-games" => early_stopping.evaluation_games = value()?.parse()?,
+            "--patience" => early_stopping.patience = value()?.parse::<usize>()?.max(1),
+            "--min-improvement" => early_stopping.min_improvement = value()?.parse()?,
+            "--seed" => options.seed = Some(value()?.parse()?),
+            "--output" => options.output = Some(value()?.clone()),
+            "--checkpoint-every" => checkpoint_interval = Some(value()?.parse::<usize>()?),
+            "--checkpoint-keep" => checkpoint_keep = value()?.parse::<usize>()?.max(1),
+            "--resume" => resume = Some(value()?.clone()),
+            "--metrics" => metrics_path = Some(value()?.clone()),
+            "--metrics-interval" => metrics_interval = value()?.parse()?,
+            "--alpha-schedule" => {
+                alpha_schedule = Some(match value()?.as_str() {
+                    "constant" => AlphaSchedule::Constant,
+                    "visits" => AlphaSchedule::InverseVisits,
+                    other => return Err(format!("unknown alpha schedule '{}'", other).into()),
+                })
+            }
+            "--exploration" => {
+                let spec = value()?;
+                exploration = Some(match spec.split_once('@') {
+                    None if spec == "epsilon" => Exploration::EpsilonGreedy,
+                    None if spec == "ucb" => Exploration::Ucb { c: std::f64::consts::SQRT_2 },
+                    Some(("ucb", c)) => Exploration::Ucb { c: c.parse()? },
+                    _ => return Err(format!("unknown exploration '{}'", spec).into()),
+                })
+            }
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
+    if let Some(curriculum) = options.curriculum.as_mut() {
+        if let Some((start, end)) = curriculum_rate {
+            curriculum.start_rate = start;
+            curriculum.end_rate = end;
+        }
+        curriculum.review_interval = curriculum_review.unwrap_or(curriculum.review_interval).max(1);
+    }
+    if let Some(capacity) = replay_capacity {
+        if capacity == 0 || replay_ratio.is_nan() || replay_ratio < 0.0 {
+            return Err("replay capacity must be positive and the replay ratio non-negative".into());
+        }
+        options.replay = Some(ReplayBuffer::new(capacity, replay_sampling, replay_ratio));
+    }
+    if use_early_stopping {
+        options.early_stopping = Some(early_stopping);
+    }
+    if let Some(path) = metrics_path {
+        options.metrics = Some(MetricsLog::create(&path, metrics_interval)?);
+    }
+    if let Some(path) = resume {
+        agent = QLearningAgent::load_from_file(&path)?;
+        options.resume = true;
+        if board.is_some_and(|board| board != agent.metadata().board) {
+            return Err(format!("{} was trained on {}, not {}", path, agent.metadata().board, board.unwrap()).into());
+        }
+        board = Some(agent.metadata().board);
+        if rules.is_some_and(|rules| rules != agent.metadata().rules) {
+            return Err(format!("{} was trained for {} rules, not {}", path, agent.metadata().rules, rules.unwrap()).into());
+        }
+        rules = Some(agent.metadata().rules);
+    }
+    // set after any resume, so they also apply to the loaded agent
+    if let Some(alpha_schedule) = alpha_schedule {
+        agent.set_alpha_schedule(alpha_schedule);
+    }
+    if let Some(exploration) = exploration {
+        agent.set_exploration(exploration);
+    }
+    options.board = board.unwrap_or(BoardSize::CLASSIC);
+    options.rules = rules.unwrap_or_default();
+    options.rules.check_board(options.board)?;
+    if options.rules != Rules::Standard
+        && (options.curriculum.is_some()
+            || options.replay.is_some()
+            || !options.opponents.is_self_play()
+            || options.early_stopping.as_ref().is_some_and(|early_stopping| early_stopping.evaluation_interval.is_some()))
+    {
+        return Err(format!("{} rules are learned by self-play alone, without curricula, replay, opponents or evaluation", options.rules).into());
+    }
+    if options.curriculum.is_some() && options.board != BoardSize::CLASSIC {
+        return Err("curricula only hold 3x3 positions".into());
+    }
+    options.opponents.check_board(options.board)?;
+    if let Some(early_stopping) = &options.early_stopping
+        && early_stopping.evaluation_interval.is_some()
+    {
+        early_stopping.opponent.check_board(options.board)?;
+    }
+    if let Some(interval) = checkpoint_interval {
+        let output = options.output.as_deref().unwrap_or(FILENAME);
+        options.checkpoints = Some(Checkpoints::new(output, interval.max(1), checkpoint_keep));
+    }
+    train_q_learning(&mut agent, &mut options);
+    Ok(())
+}
+
+/// `train-network [--episodes N] [--board WIDTHxHEIGHT[:K]] [--hidden N[,N...]] [--learning-rate X]
+/// [--gamma X] [--epsilon X] [--batch N] [--replay CAPACITY] [--target-every N] [--opponents SPEC]
+/// [--seed N] [--output FILE]` trains a network agent by deep Q-learning and saves it, by default
+/// to `network.json`. On the 3×3 board it is then audited for perfect play.
+fn run_network_training(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut options = TrainingOptions::new(dqn::DEFAULT_EPISODES);
+    options.output = Some(dqn::NETWORK_FILENAME.to_string());
+    let mut hidden = dqn::DEFAULT_HIDDEN.to_vec();
+    let mut agent = DqnAgent::new(BoardSize::CLASSIC, &hidden);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--episodes" => options.episodes = value()?.parse()?,
+            "--board" => options.board = BoardSize::parse(value()?)?,
+            "--hidden" => hidden = value()?.split(',').map(str::parse).collect::<Result<_, _>>()?,
+            "--learning-rate" => agent.learning_rate = value()?.parse()?,
+            "--gamma" => agent.gamma = value()?.parse()?,
+            "--epsilon" => agent.epsilon = value()?.parse()?,
+            "--batch" => agent.batch_size = value()?.parse()?,
+            "--replay" => agent.replay_capacity = value()?.parse()?,
+            "--target-every" => agent.target_interval = value()?.parse()?,
+            "--opponents" => options.opponents = OpponentPool::parse(value()?)?,
+            "--seed" => options.seed = Some(value()?.parse()?),
+            "--output" => options.output = Some(value()?.clone()),
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
+    if hidden.contains(&0) || agent.batch_size == 0 || agent.target_interval == 0 || agent.replay_capacity < agent.batch_size {
+        return Err("layer sizes, the batch size and the target interval must be positive, and the replay memory hold a batch".into());
+    }
+    if options.output.as_ref().is_some_and(|output| output.ends_with(&format!(".{}", model::BINARY_EXTENSION))) {
+        return Err("network agents are only saved as JSON".into());
+    }
+    agent.network = DqnAgent::new(options.board, &hidden).network;
+    train_dqn(&mut agent, &mut options)?;
+    if options.board == BoardSize::CLASSIC {
+        let audit = opponent::audit(&mut agent);
+        println!("Perfect play audit: {} mistakes in {} positions", audit.mistakes, audit.positions);
+    }
+    Ok(())
+}
+
+/// `coverage [FILE]` reports which positions a saved agent has experienced.
+fn run_coverage(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let path = args.first().map_or(FILENAME, String::as_str);
+    stats::print_coverage(&QLearningAgent::load_from_file(path)?);
+    Ok(())
+}
+
+/// `inspect [FILE]` prints the header of a saved model without loading its Q-table.
+fn run_inspect(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let path = args.first().map_or(FILENAME, String::as_str);
+    print!("{}", model::read_header(path)?);
+    Ok(())
+}
+
+/// `convert INPUT OUTPUT` rewrites a model in the format chosen by the output's extension:
+/// binary for `.bin`, JSON otherwise. Either direction is lossless.
+fn run_convert(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let [input, output] = args else {
+        return Err("usage: convert INPUT OUTPUT".into());
+    };
+    let agent = QLearningAgent::load_from_file(input)?;
+    agent.save_to_path(output)?;
+    println!("Converted {} ({} states) to {}", input, agent.q_table().len(), output);
+    Ok(())
+}
+
+/// `merge [--strategy mean|max|visits] [--output FILE] MODEL...` combines the Q-tables of
+/// several agents into one. The output defaults to the model file.
+fn run_merge(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut strategy = MergeStrategy::Mean;
+    let mut output = FILENAME.to_string();
+    let mut models = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--strategy" => strategy = MergeStrategy::parse(value()?)?,
+            "--output" => output = value()?.clone(),
+            _ if arg.starts_with("--") => return Err(format!("unknown argument '{}'", arg).into()),
+            _ => models.push(QLearningAgent::load_from_file(arg)?),
+        }
+    }
+    let merged = table::merge(&models, strategy)?;
+    merged.save_to_path(&output)?;
+    println!("Merged {} models into {} ({} states)", models.len(), output, merged.q_table().len());
+    Ok(())
+}
+
+/// `diff [--limit N] FIRST SECOND` compares the Q-tables of two agents.
+fn run_diff(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut limit = 10;
+    let mut models = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--limit" => limit = value()?.parse()?,
+            _ if arg.starts_with("--") => return Err(format!("unknown argument '{}'", arg).into()),
+            _ => models.push(arg.clone()),
+        }
+    }
+    let [first, second] = models.as_slice() else {
+        return Err("usage: diff [--limit N] FIRST SECOND".into());
+    };
+    let mut diff = table::diff(&QLearningAgent::load_from_file(first)?, &QLearningAgent::load_from_file(second)?)?;
+    diff.limit = limit;
+    print!("{}", diff);
+    Ok(())
+}
+
+/// `codegen [--language rust|c] [--model FILE] [--output FILE]` writes the greedy policy of a
+/// saved agent as a constant lookup table. The language defaults to C for `.h` outputs and
+/// Rust otherwise, and the code goes to stdout without `--output`.
+fn run_codegen(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut language = None;
+    let mut model = FILENAME.to_string();
+    let mut output = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--language" => language = Some(codegen::Language::parse(value()?)?),
+            "--model" => model = value()?.clone(),
+            "--output" => output = Some(value()?.clone()),
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
+    let agent = QLearningAgent::load_from_file(&model)?;
+    agent.metadata().check_standard_rules().map_err(|err| format!("{}: {}", model, err))?;
+    if agent.metadata().board != BoardSize::CLASSIC {
+        return Err(format!("{} was trained on {}; only 3x3 policies can be generated", model, agent.metadata().board).into());
+    }
+    let policy = codegen::policy(&agent);
+    if policy.unknown > 0 {
+        eprintln!("{} reachable positions are not in the Q-table and play the first empty cell", policy.unknown);
+    }
+    match output {
+        Some(output) => {
+            let language = language.unwrap_or_else(|| codegen::Language::from_path(&output));
+            std::fs::write(&output, codegen::generate(&policy, language, &model))?;
+            eprintln!("Wrote the policy of {} to {}", model, output);
+        }
+        None => print!("{}", codegen::generate(&policy, language.unwrap_or(codegen::Language::Rust), &model)),
+    }
+    Ok(())
+}
+
+/// `dot [--model FILE] [--state STATE] [--first x|o] [--depth N] [--optimal] [--output FILE]`
+/// writes the game tree below a `board_state` as a Graphviz graph. The state must fit the board
+/// the model was trained on and defaults to its empty board. `--first` picks the side to move
+/// when both have played equally often, which defaults to X.
+fn run_dot(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut model = FILENAME.to_string();
+    let mut state = None;
+    let mut first = Cell::X;
+    let mut depth = 2;
+    let mut optimal = false;
+    let mut output = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--model" => model = value()?.clone(),
+            "--state" => state = Some(value()?.clone()),
+            "--first" => {
+                first = match value()?.as_str() {
+                    "x" | "X" => Cell::X,
+                    "o" | "O" => Cell::O,
+                    other => return Err(format!("unknown side '{}'", other).into()),
+                }
+            }
+            "--depth" => depth = value()?.parse()?,
+            "--optimal" => optimal = true,
+            "--output" => output = Some(value()?.clone()),
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
+    let agent = QLearningAgent::load_from_file(&model)?;
+    agent.metadata().check_standard_rules().map_err(|err| format!("{}: {}", model, err))?;
+    let size = agent.metadata().board;
+    let state = state.unwrap_or_else(|| "-".repeat(size.cells()));
+    let mut root = Board::from_state_with_size(size, &state)
+        .ok_or_else(|| format!("invalid board state '{}' for a {} board", state, size))?;
+    if state.matches('X').count() == state.matches('O').count() {
+        root.set_current_player(first);
+    }
+    let graph = dot::game_tree(&agent, &root, depth, optimal);
+    match output {
+        Some(output) => std::fs::write(output, graph)?,
+        None => print!("{}", graph),
+    }
+    Ok(())
+}
+
+/// `play [--mode pvp|pva] [--model FILE] [--ai x|o] [--board WIDTHxHEIGHT[:K]]` plays in the
+/// terminal instead of the GUI. In PvA mode the saved agent plays O unless `--ai` says
+/// otherwise, on the board it was trained on.
+fn run_play(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut mode = GameMode::PvA;
+    let mut model = FILENAME.to_string();
+    let mut ai_side = Cell::O;
+    let mut board = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--mode" => {
+                mode = match value()?.as_str() {
+                    "pvp" => GameMode::PvP,
+                    "pva" => GameMode::PvA,
+                    other => return Err(format!("unknown mode '{}'", other).into()),
+                }
+            }
+            "--model" => model = value()?.clone(),
+            "--board" => board = Some(BoardSize::parse(value()?)?),
+            "--ai" => {
+                ai_side = match value()?.as_str() {
+                    "x" | "X" => Cell::X,
+                    "o" | "O" => Cell::O,
+                    other => return Err(format!("unknown side '{}'", other).into()),
+                }
+            }
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
+    let agent = if mode == GameMode::PvA {
+        QLearningAgent::load_from_file(&model).map_err(|err| format!("{} (run `train` first): {}", model, err))?
+    } else {
+        QLearningAgent::new(0.08,0.7,0.9)
+    };
+    if mode == GameMode::PvA {
+        agent.metadata().check_standard_rules().map_err(|err| format!("{}: {}", model, err))?;
+    }
+    if mode == GameMode::PvA && board.is_some_and(|board| board != agent.metadata().board) {
+        return Err(format!("{} was trained on {}", model, agent.metadata().board).into());
+    }
+    let size = if mode == GameMode::PvA { agent.metadata().board } else { board.unwrap_or(BoardSize::CLASSIC) };
+    terminal::play(mode, agent, ai_side, size, &mut std::io::stdin().lock(), &mut std::io::stdout().lock())?;
+    Ok(())
+}
+
+/// `engine [--model FILE] [--engine KIND] [--board WxH[:K]]` speaks the line protocol of
+/// `engine` on stdin and stdout, on the classic board unless another is given. The saved agent
+/// plays without exploring unless another opponent kind is given.
+fn run_engine(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut model = FILENAME.to_string();
+    let mut kind = None;
+    let mut board = BoardSize::CLASSIC;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--model" => model = value()?.clone(),
+            "--board" => board = BoardSize::parse(value()?)?,
+            "--engine" => kind = Some(value()?.clone()),
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
+    let mut engine = Opponent::parse(&kind.unwrap_or_else(|| format!("snapshot@{}", model)))?;
+    engine.check_board(board)?;
+    engine::run(&mut engine, board, &mut std::io::stdin().lock(), &mut std::io::stdout().lock())?;
+    Ok(())
+}
+
+/// `match [--board WxH[:K]] [--x KIND] [--o KIND] [--games N] [--seed N]` plays on the classic
+/// board unless `--board` is given, between two computer players of any opponent kind, e.g.
+/// `mcts@2000+reuse` or `minimax`, and reports the results. X always starts.
+fn run_match(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut board = BoardSize::CLASSIC;
+    let mut kinds = ["mcts".to_string(), "random".to_string()];
+    let mut games = 10;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--board" => board = BoardSize::parse(value()?)?,
+            "--x" => kinds[0] = value()?.clone(),
+            "--o" => kinds[1] = value()?.clone(),
+            "--games" => games = value()?.parse()?,
+            "--seed" => random::seed(value()?.parse()?),
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
+    let opponents = [Opponent::parse(&kinds[0])?, Opponent::parse(&kinds[1])?];
+    for opponent in &opponents {
+        opponent.check_board(board)?;
+    }
+    let mut players = opponents.map(|opponent| Box::new(opponent) as Box<dyn Agent<Board>>);
+    let new_game = || {
+        let mut game = Board::with_size(board);
+        game.set_current_player(Cell::X);
+        game
+    };
+    play_match(new_game, &mut players, &kinds, games);
+    Ok(())
+}
+
+/// `qubic [--x KIND] [--o KIND] [--games N] [--seed N]` plays Qubic between two computer
+/// players, `random`, `heuristic` or `mcts[@OPTIONS]`, and reports the results. X always starts.
+fn run_qubic(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut kinds = ["heuristic".to_string(), "random".to_string()];
+    let mut games = 10;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--x" => kinds[0] = value()?.clone(),
+            "--o" => kinds[1] = value()?.clone(),
+            "--games" => games = value()?.parse()?,
+            "--seed" => random::seed(value()?.parse()?),
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
+    let mut players = [match_player(&kinds[0], QubicOpponent::parse)?, match_player(&kinds[1], QubicOpponent::parse)?];
+    let new_game = || {
+        let mut game = QubicBoard::new();
+        game.current_player = 0;
+        game
+    };
+    play_match(new_game, &mut players, &kinds, games);
+    Ok(())
+}
+
+/// `ultimate [--x KIND] [--o KIND] [--games N] [--seed N]` plays ultimate tic-tac-toe between two
+/// computer players, `random`, `search[@DEPTH]` or `mcts[@OPTIONS]`, and reports the results.
+/// X always starts.
+fn run_ultimate(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut kinds = ["search".to_string(), "random".to_string()];
+    let mut games = 10;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--x" => kinds[0] = value()?.clone(),
+            "--o" => kinds[1] = value()?.clone(),
+            "--games" => games = value()?.parse()?,
+            "--seed" => random::seed(value()?.parse()?),
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
+    let mut players = [match_player(&kinds[0], UltimateOpponent::parse)?, match_player(&kinds[1], UltimateOpponent::parse)?];
+    let new_game = || {
+        let mut game = UltimateBoard::new();
+        game.current_player = 0;
+        game
+    };
+    play_match(new_game, &mut players, &kinds, games);
+    Ok(())
+}
+
+/// Parses a match player: `mcts[@OPTIONS]`, which plays every game, or a kind `parse` knows.
+fn match_player<G: Game + 'static, A: Agent<G> + 'static>(
+    kind: &str,
+    parse: fn(&str) -> Result<A, Box<dyn std::error::Error>>,
+) -> Result<Box<dyn Agent<G>>, Box<dyn std::error::Error>> {
+    match kind.split_once('@').map_or((kind, None), |(name, options)| (name, Some(options))) {
+        ("mcts", options) => Ok(Box::new(Mcts::<G>::parse(options)?)),
+        _ => Ok(Box::new(parse(kind)?)),
+    }
+}
+
+/// Plays `games` games between `players` and prints the results.
+fn play_match<G: Game>(new_game: impl Fn() -> G, players: &mut [Box<dyn Agent<G>>; 2], kinds: &[String; 2], games: usize) {
+    let (mut x_wins, mut o_wins, mut draws) = (0, 0, 0);
+    for _ in 0..games {
+        let mut game = new_game();
+        while !game.outcome().0 {
+            let player = if game.to_move() == Cell::X { 0 } else { 1 };
+            let action = players[player].choose_move(&game);
+            game.play(action);
+        }
+        match game.outcome().1 {
+            Some(Cell::X) => x_wins += 1,
+            Some(_) => o_wins += 1,
+            None => draws += 1,
+        }
+    }
+    println!("X ({}) won {}, O ({}) won {}, {} drawn", kinds[0], x_wins, kinds[1], o_wins, draws);
+}
+
+/// `prune --min-visits N [--model FILE] [--output FILE]` drops rarely updated entries from a
+/// saved agent. The output defaults to overwriting the model.
+fn run_prune(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut min_visits = None;
+    let mut model = FILENAME.to_string();
+    let mut output = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--min-visits" => min_visits = Some(value()?.parse::<u64>()?),
+            "--model" => model = value()?.clone(),
+            "--output" => output = Some(value()?.clone()),
+            _ => return Err(format!("unknown argument '{}'", arg).into()),
+        }
+    }
+    let min_visits = min_visits.ok_or("--min-visits is required")?;
+    let mut agent = QLearningAgent::load_from_file(&model)?;
+    let removed = agent.prune(min_visits);
+    let output = output.unwrap_or(model);
+    agent.save_to_path(&output)?;
+    println!("Removed {} entries, {} states remain; saved to {}", removed, agent.q_table().len(), output);
+    Ok(())
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command: Option<CliCommand> = match args.first().map(String::as_str) {
+        Some("train") => Some(run_training),
+        Some("train-network") => Some(run_network_training),
+        Some("coverage") => Some(run_coverage),
+        Some("prune") => Some(run_prune),
+        Some("inspect") => Some(run_inspect),
+        Some("convert") => Some(run_convert),
+        Some("merge") => Some(run_merge),
+        Some("diff") => Some(run_diff),
+        Some("codegen") => Some(run_codegen),
+        Some("dot") => Some(run_dot),
+        Some("play") => Some(run_play),
+        Some("engine") => Some(run_engine),
+        Some("match") => Some(run_match),
+        Some("ultimate") => Some(run_ultimate),
+        Some("qubic") => Some(run_qubic),
+        _ => None,
+    };
+    if let Some(command) = command {
+        if let Err(err) = command(&args[1..]) {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+    let options = match GuiOptions::parse(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }
+    };
+    // boards whose cells cannot shrink enough get a taller window
     let settings = Settings {
         antialiasing: true,
         window: iced::window::Settings {
-            size: iced::Size::new(400.0,600.0),
+            size: window_size(grid_height(options.board)),
             resizable: false,
             decorations: true,
             ..Default::default()
         },
-        ..Settings::default()
+        ..Settings::with_flags(options)
     };
     TicTacToeApp::run(settings).unwrap();
 }
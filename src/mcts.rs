@@ -0,0 +1,341 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! Monte Carlo tree search with UCT, for any `Game`.
+//!
+//! Every iteration walks down the tree by the children's upper confidence bounds, adds one
+//! untried move, plays the game out from there and credits the result to the nodes it passed.
+//! The move played is the root's most visited child. Nothing is learned ahead of time, so it
+//! plays boards whose positions a Q-table could never hold.
+
+use crate::game::{Agent, Game};
+use crate::{random, Cell, Player};
+use rand::prelude::{IndexedRandom, SliceRandom};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// How long a search runs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Budget {
+    Iterations(usize),
+    /// At least one iteration is run however short the time.
+    Time(Duration),
+}
+
+/// How games are played out from a new node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rollout {
+    /// Uniformly random moves.
+    Random,
+    /// Wins when it can, blocks when it must and plays randomly otherwise.
+    Heuristic,
+}
+
+#[derive(Debug)]
+struct Node<M> {
+    /// The move leading here, `None` at the root.
+    action: Option<M>,
+    /// Who made `action`; `score` counts results from their side.
+    mover: Cell,
+    children: Vec<usize>,
+    /// Moves not expanded yet, in random order.
+    untried: Vec<M>,
+    visits: u32,
+    /// One for each win and a half for each draw.
+    score: f64,
+}
+
+impl<M> Node<M> {
+    fn new(action: Option<M>, mover: Cell, mut untried: Vec<M>) -> Self {
+        untried.shuffle(&mut random::rng());
+        Node { action, mover, children: Vec::new(), untried, visits: 0, score: 0.0 }
+    }
+}
+
+/// The MCTS agent. Configure it through the public fields.
+#[derive(Debug)]
+pub struct Mcts<G: Game> {
+    pub budget: Budget,
+    /// The exploration constant of the upper confidence bound.
+    pub exploration: f64,
+    pub rollout: Rollout,
+    /// Keeps the subtree of the position reached after each move, so the next search starts
+    /// from the statistics gathered under it.
+    pub reuse_tree: bool,
+    nodes: Vec<Node<G::Move>>,
+    root: usize,
+    /// The position at `nodes[root]`.
+    root_game: Option<G>,
+}
+
+impl<G: Game> Mcts<G> {
+    pub const DEFAULT_ITERATIONS: usize = 1000;
+
+    pub fn new(budget: Budget) -> Self {
+        Mcts {
+            budget,
+            exploration: std::f64::consts::SQRT_2,
+            rollout: Rollout::Random,
+            reuse_tree: false,
+            nodes: Vec::new(),
+            root: 0,
+            root_game: None,
+        }
+    }
+
+    /// Parses the options of `mcts@OPTIONS`: `+` separated among a number of iterations, a time
+    /// budget like `200ms` or `2s`, `heuristic` for heuristic rollouts and `reuse` to keep the
+    /// tree between moves, e.g. `5000+heuristic+reuse`. Without options it runs
+    /// `DEFAULT_ITERATIONS` iterations of random rollouts.
+    pub fn parse(options: Option<&str>) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut mcts = Mcts::new(Budget::Iterations(Self::DEFAULT_ITERATIONS));
+        for option in options.into_iter().flat_map(|options| options.split('+')) {
+            mcts.budget = match option {
+                "heuristic" => {
+                    mcts.rollout = Rollout::Heuristic;
+                    continue;
+                }
+                "reuse" => {
+                    mcts.reuse_tree = true;
+                    continue;
+                }
+                _ => {
+                    if let Some(ms) = option.strip_suffix("ms") {
+                        Budget::Time(Duration::from_millis(ms.parse()?))
+                    } else if let Some(secs) = option.strip_suffix('s') {
+                        Budget::Time(Duration::try_from_secs_f64(secs.parse()?).map_err(|_| format!("invalid mcts time '{}'", option))?)
+                    } else {
+                        Budget::Iterations(option.parse().map_err(|_| format!("unknown mcts option '{}'", option))?)
+                    }
+                }
+            };
+        }
+        if matches!(mcts.budget, Budget::Iterations(0)) || mcts.budget == Budget::Time(Duration::ZERO) {
+            return Err("the mcts budget must be greater than zero".into());
+        }
+        Ok(mcts)
+    }
+
+    /// Searches `game` within the budget and returns the most visited move; the game must not
+    /// be over.
+    pub fn search(&mut self, game: &G) -> G::Move {
+        self.find_root(game);
+        let start = Instant::now();
+        let mut iterations = 0;
+        loop {
+            self.iterate();
+            iterations += 1;
+            let done = match self.budget {
+                Budget::Iterations(limit) => iterations >= limit,
+                Budget::Time(limit) => start.elapsed() >= limit,
+            };
+            if done {
+                break;
+            }
+        }
+        let best = *self.nodes[self.root].children.iter().max_by_key(|&&child| self.nodes[child].visits).unwrap();
+        let action = self.nodes[best].action.unwrap();
+        if self.reuse_tree {
+            let mut next = game.clone();
+            next.play(action);
+            self.reroot(best, next);
+        }
+        action
+    }
+
+    /// Points the root at `game`: the current root when it is that position already or one of
+    /// its children when it is a move on, which is where the opponent's reply leaves a reused
+    /// tree. Anything else starts a new tree.
+    fn find_root(&mut self, game: &G) {
+        if self.reuse_tree
+            && let Some(root_game) = &self.root_game
+        {
+            let key = (game.key(), game.to_move());
+            if (root_game.key(), root_game.to_move()) == key {
+                return;
+            }
+            for &child in &self.nodes[self.root].children {
+                let mut next = root_game.clone();
+                next.play(self.nodes[child].action.unwrap());
+                if (next.key(), next.to_move()) == key {
+                    self.reroot(child, next);
+                    return;
+                }
+            }
+        }
+        self.nodes.clear();
+        self.nodes.push(Node::new(None, Player::new(game.to_move()).opponent().marker, game.legal_moves()));
+        self.root = 0;
+        self.root_game = Some(game.clone());
+    }
+
+    /// Makes `node`, the position `game`, the root. The rest of the tree can never be reached
+    /// again, so the arena is compacted to the subtree under `node`, keeping its order.
+    fn reroot(&mut self, node: usize, game: G) {
+        let mut old: Vec<Option<Node<G::Move>>> = std::mem::take(&mut self.nodes).into_iter().map(Some).collect();
+        self.nodes.push(old[node].take().unwrap());
+        let mut next = 0;
+        while next < self.nodes.len() {
+            let children = std::mem::take(&mut self.nodes[next].children);
+            self.nodes[next].children = (self.nodes.len()..self.nodes.len() + children.len()).collect();
+            self.nodes.extend(children.into_iter().map(|child| old[child].take().unwrap()));
+            next += 1;
+        }
+        self.root = 0;
+        self.root_game = Some(game);
+    }
+
+    /// One round of selection, expansion, rollout and backpropagation.
+    fn iterate(&mut self) {
+        let mut game = self.root_game.clone().unwrap();
+        let mut node = self.root;
+        let mut path = vec![node];
+        while self.nodes[node].untried.is_empty() && !self.nodes[node].children.is_empty() {
+            node = self.select_child(node);
+            game.play(self.nodes[node].action.unwrap());
+            path.push(node);
+        }
+        if let Some(action) = self.nodes[node].untried.pop() {
+            let mover = game.to_move();
+            game.play(action);
+            let child = self.nodes.len();
+            self.nodes.push(Node::new(Some(action), mover, game.legal_moves()));
+            self.nodes[node].children.push(child);
+            path.push(child);
+        }
+        let winner = self.play_out(&mut game);
+        for node in path {
+            let node = &mut self.nodes[node];
+            node.visits += 1;
+            node.score += match winner {
+                Some(winner) if winner == node.mover => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+        }
+    }
+
+    /// The child with the highest upper confidence bound.
+    fn select_child(&self, node: usize) -> usize {
+        let log_visits = (self.nodes[node].visits as f64).ln();
+        let bound = |child: usize| {
+            let child = &self.nodes[child];
+            let visits = child.visits as f64;
+            child.score / visits + self.exploration * (log_visits / visits).sqrt()
+        };
+        *self.nodes[node].children.iter().max_by(|&&a, &&b| bound(a).total_cmp(&bound(b))).unwrap()
+    }
+
+    /// Plays `game` to the end and returns the winner.
+    fn play_out(&self, game: &mut G) -> Option<Cell> {
+        let mut rng = random::rng();
+        loop {
+            let (game_over, winner) = game.outcome();
+            if game_over {
+                return winner;
+            }
+            let action = match self.rollout {
+                Rollout::Heuristic => game.winning_move().or_else(|| game.blocking_move()),
+                Rollout::Random => None,
+            };
+            let action = action.unwrap_or_else(|| *game.legal_moves().choose(&mut rng).unwrap());
+            game.play(action);
+        }
+    }
+}
+
+impl<G: Game> Agent<G> for Mcts<G> {
+    fn choose_move(&mut self, game: &G) -> G::Move {
+        self.search(game)
+    }
+}
+
+impl<G: Game> fmt::Display for Mcts<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.budget {
+            Budget::Iterations(iterations) => write!(f, "MCTS, {} iterations", iterations)?,
+            Budget::Time(time) => write!(f, "MCTS, {:?} per move", time)?,
+        }
+        if self.rollout == Rollout::Heuristic {
+            write!(f, ", heuristic rollouts")?;
+        }
+        if self.reuse_tree {
+            write!(f, ", tree reuse")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Board;
+
+    /// The nodes reachable from the root.
+    fn tree_size<G: Game>(mcts: &Mcts<G>) -> usize {
+        let mut stack = vec![mcts.root];
+        let mut size = 0;
+        while let Some(node) = stack.pop() {
+            size += 1;
+            stack.extend(&mcts.nodes[node].children);
+        }
+        size
+    }
+
+    #[test]
+    fn bad_time_budgets_are_refused() {
+        for options in ["-1s", "nans", "infs", "1e300s"] {
+            assert!(Mcts::<Board>::parse(Some(options)).is_err(), "accepted {}", options);
+        }
+        assert_eq!(Mcts::<Board>::parse(Some("0.5s")).unwrap().budget, Budget::Time(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn never_loses_to_random() {
+        random::seed(49);
+        let mut mcts = Mcts::<Board>::parse(Some("2000+reuse")).unwrap();
+        for game_number in 0..40 {
+            let mcts_marker = if game_number % 2 == 0 { Cell::X } else { Cell::O };
+            let mut game = Board::new();
+            game.set_current_player(Cell::X);
+            while !game.is_game_over().0 {
+                let (row, col) = if game.get_current_player().marker == mcts_marker {
+                    mcts.search(&game)
+                } else {
+                    *game.available_moves().choose(&mut random::rng()).unwrap()
+                };
+                game.make_move(row, col);
+            }
+            assert_ne!(game.check_winner(), Some(Player::new(mcts_marker).opponent().marker), "mcts lost: {}", game);
+        }
+    }
+
+    #[test]
+    fn reused_tree_follows_the_reply_and_drops_the_rest() {
+        random::seed(49);
+        let mut mcts = Mcts::<Board>::parse(Some("500+reuse")).unwrap();
+        let mut game = Board::new();
+        game.set_current_player(Cell::X);
+        let (row, col) = mcts.search(&game);
+        game.make_move(row, col);
+        assert_eq!((mcts.root, mcts.nodes.len()), (0, tree_size(&mcts)));
+
+        let reply = game.available_moves()[0];
+        let visits = mcts.nodes[mcts.root]
+            .children
+            .iter()
+            .map(|&child| &mcts.nodes[child])
+            .find(|child| child.action == Some(reply))
+            .map_or(0, |child| child.visits);
+        assert!(visits > 0);
+        game.make_move(reply.0, reply.1);
+        mcts.find_root(&game);
+        let root_game = mcts.root_game.as_ref().unwrap();
+        assert_eq!((root_game.key(), root_game.to_move()), (game.key(), game.to_move()));
+        assert_eq!(mcts.nodes[mcts.root].visits, visits);
+        assert_eq!((mcts.root, mcts.nodes.len()), (0, tree_size(&mcts)));
+    }
+}
@@ -0,0 +1,287 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+use crate::convergence::ChangeWindow;
+use crate::{Cell, QLearningAgent};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// What happened during a single training episode.
+#[derive(Debug, Default)]
+pub struct EpisodeStats {
+    pub explored: i64,
+    pub exploited: i64,
+    pub blocking_moves: i64,
+    /// Moves played by either side, not counting the pieces of a curriculum position.
+    pub moves: usize,
+    pub winner: Option<Cell>,
+    /// The side the agent played, or `None` when it played both.
+    pub agent_side: Option<Cell>,
+}
+
+impl EpisodeStats {
+    pub fn record_move(&mut self, explore: bool, blocking: bool) {
+        if explore { self.explored += 1 } else { self.exploited += 1 }
+        if blocking {
+            self.blocking_moves += 1;
+        }
+        self.moves += 1;
+    }
+}
+
+/// One line of the metrics log, summarising the episodes since the previous line.
+/// Results are counted per side from the agent's point of view; in self-play a game counts for both.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsRow {
+    pub episode: usize,
+    pub epsilon: f64,
+    /// The mean learning rate of the updates made, which differs from the agent's `alpha`
+    /// under a count-based schedule.
+    pub alpha: f64,
+    pub x_wins: usize,
+    pub x_draws: usize,
+    pub x_losses: usize,
+    pub o_wins: usize,
+    pub o_draws: usize,
+    pub o_losses: usize,
+    pub avg_game_length: f64,
+    pub q_table_size: usize,
+    pub mean_abs_dq: f64,
+    pub blocking_move_frequency: f64,
+}
+
+const CSV_HEADER: &str = "episode,epsilon,alpha,x_wins,x_draws,x_losses,o_wins,o_draws,o_losses,\
+avg_game_length,q_table_size,mean_abs_dq,blocking_move_frequency";
+
+impl MetricsRow {
+    fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.episode,
+            self.epsilon,
+            self.alpha,
+            self.x_wins,
+            self.x_draws,
+            self.x_losses,
+            self.o_wins,
+            self.o_draws,
+            self.o_losses,
+            self.avg_game_length,
+            self.q_table_size,
+            self.mean_abs_dq,
+            self.blocking_move_frequency
+        )
+    }
+
+    fn from_csv(line: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != 13 {
+            return Err(format!("expected 13 fields, got {}", fields.len()).into());
+        }
+        Ok(MetricsRow {
+            episode: fields[0].parse()?,
+            epsilon: fields[1].parse()?,
+            alpha: fields[2].parse()?,
+            x_wins: fields[3].parse()?,
+            x_draws: fields[4].parse()?,
+            x_losses: fields[5].parse()?,
+            o_wins: fields[6].parse()?,
+            o_draws: fields[7].parse()?,
+            o_losses: fields[8].parse()?,
+            avg_game_length: fields[9].parse()?,
+            q_table_size: fields[10].parse()?,
+            mean_abs_dq: fields[11].parse()?,
+            blocking_move_frequency: fields[12].parse()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetricsFormat {
+    Csv,
+    JsonLines,
+}
+
+impl MetricsFormat {
+    /// `.csv` files are written as CSV, anything else as JSON Lines.
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => MetricsFormat::Csv,
+            _ => MetricsFormat::JsonLines,
+        }
+    }
+}
+
+/// Counters for the episodes since the last written row.
+#[derive(Debug, Default)]
+struct Window {
+    episodes: usize,
+    moves: usize,
+    agent_moves: i64,
+    blocking_moves: i64,
+    /// Wins, draws and losses for the agent playing X, then O.
+    results: [[usize; 3]; 2],
+    changes: ChangeWindow,
+}
+
+enum Sink {
+    File { writer: BufWriter<File>, format: MetricsFormat },
+    Callback(Box<dyn FnMut(MetricsRow)>),
+}
+
+/// Produces a row of training metrics every `interval` episodes, either written to a file or
+/// handed to a callback.
+pub struct MetricsLog {
+    sink: Sink,
+    interval: usize,
+    window: Window,
+}
+
+impl MetricsLog {
+    pub fn create(path: &str, interval: usize) -> io::Result<Self> {
+        let format = MetricsFormat::from_path(path);
+        let mut writer = BufWriter::new(File::create(path)?);
+        if format == MetricsFormat::Csv {
+            writeln!(writer, "{}", CSV_HEADER)?;
+        }
+        Ok(MetricsLog { sink: Sink::File { writer, format }, interval: interval.max(1), window: Window::default() })
+    }
+
+    pub fn callback(interval: usize, callback: impl FnMut(MetricsRow) + 'static) -> Self {
+        MetricsLog { sink: Sink::Callback(Box::new(callback)), interval: interval.max(1), window: Window::default() }
+    }
+
+    /// Adds a finished episode, writing a row once `interval` episodes have been collected.
+    pub fn record(&mut self, episodes_played: usize, agent: &QLearningAgent, stats: &EpisodeStats, changes: &ChangeWindow) -> io::Result<()> {
+        let window = &mut self.window;
+        window.episodes += 1;
+        window.moves += stats.moves;
+        window.agent_moves += stats.explored + stats.exploited;
+        window.blocking_moves += stats.blocking_moves;
+        window.changes.merge(changes);
+        let sides = match stats.agent_side {
+            Some(side) => vec![side],
+            None => vec![Cell::X, Cell::O],
+        };
+        for side in sides {
+            let result = match stats.winner {
+                Some(winner) if winner == side => 0,
+                None => 1,
+                Some(_) => 2,
+            };
+            window.results[if side == Cell::X { 0 } else { 1 }][result] += 1;
+        }
+        if episodes_played.is_multiple_of(self.interval) {
+            self.write_row(episodes_played, agent)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the remaining partial window, if any, and flushes the file.
+    pub fn finish(&mut self, episodes_played: usize, agent: &QLearningAgent) -> io::Result<()> {
+        if self.window.episodes > 0 {
+            self.write_row(episodes_played, agent)?;
+        }
+        match &mut self.sink {
+            Sink::File { writer, .. } => writer.flush(),
+            Sink::Callback(_) => Ok(()),
+        }
+    }
+
+    fn write_row(&mut self, episodes_played: usize, agent: &QLearningAgent) -> io::Result<()> {
+        let window = std::mem::take(&mut self.window);
+        let [x, o] = window.results;
+        let row = MetricsRow {
+            episode: episodes_played,
+            epsilon: agent.epsilon,
+            alpha: window.changes.mean_alpha(),
+            x_wins: x[0],
+            x_draws: x[1],
+            x_losses: x[2],
+            o_wins: o[0],
+            o_draws: o[1],
+            o_losses: o[2],
+            avg_game_length: window.moves as f64 / window.episodes.max(1) as f64,
+            q_table_size: agent.q_table.len(),
+            mean_abs_dq: window.changes.mean(),
+            blocking_move_frequency: window.blocking_moves as f64 / window.agent_moves.max(1) as f64,
+        };
+        match &mut self.sink {
+            Sink::File { writer, format: MetricsFormat::Csv } => writeln!(writer, "{}", row.to_csv()),
+            Sink::File { writer, format: MetricsFormat::JsonLines } => writeln!(writer, "{}", serde_json::to_string(&row)?),
+            Sink::Callback(callback) => {
+                callback(row);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Reads a metrics log written by `MetricsLog`, in either format.
+pub fn read_metrics(path: &str) -> Result<Vec<MetricsRow>, Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(path)?;
+    let format = MetricsFormat::from_path(path);
+    let mut rows = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() || (format == MetricsFormat::Csv && number == 0) {
+            continue;
+        }
+        let row = match format {
+            MetricsFormat::Csv => MetricsRow::from_csv(line),
+            MetricsFormat::JsonLines => serde_json::from_str(line).map_err(Into::into),
+        };
+        rows.push(row.map_err(|err| format!("{}:{}: {}", path, number + 1, err))?);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TrainingOptions, train_q_learning};
+
+    /// Trains a seeded agent for 250 episodes, logging every 100 to `path`.
+    fn log_run(path: &str) {
+        let mut agent = QLearningAgent::new(0.08, 0.7, 0.9);
+        let metrics = MetricsLog::create(path, 100).unwrap();
+        let mut options = TrainingOptions { seed: Some(31), output: None, metrics: Some(metrics), ..TrainingOptions::new(250) };
+        train_q_learning(&mut agent, &mut options);
+    }
+
+    #[test]
+    fn csv_and_json_lines_logs_hold_the_same_rows() {
+        let directory = std::env::temp_dir().join(format!("q-learning-tictactoe-metrics-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let csv = directory.join("metrics.csv").to_string_lossy().into_owned();
+        let jsonl = directory.join("metrics.jsonl").to_string_lossy().into_owned();
+        log_run(&csv);
+        log_run(&jsonl);
+        let csv_text = std::fs::read_to_string(&csv).unwrap();
+        let jsonl_text = std::fs::read_to_string(&jsonl).unwrap();
+        let (csv_rows, jsonl_rows) = (read_metrics(&csv).unwrap(), read_metrics(&jsonl).unwrap());
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let lines: Vec<&str> = csv_text.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines.len(), 4);
+        assert!(lines.iter().all(|line| line.split(',').count() == 13), "{}", csv_text);
+        let first: serde_json::Value = serde_json::from_str(jsonl_text.lines().next().unwrap()).unwrap();
+        let mut columns: Vec<&str> = CSV_HEADER.split(',').collect();
+        columns.sort_unstable();
+        assert!(first.as_object().unwrap().keys().eq(columns), "{}", first);
+
+        let episodes: Vec<usize> = csv_rows.iter().map(|row| row.episode).collect();
+        assert_eq!(episodes, [100, 200, 250]);
+        for row in &csv_rows {
+            let games = if row.episode == 250 { 50 } else { 100 };
+            assert_eq!(row.x_wins + row.x_draws + row.x_losses, games, "{:?}", row);
+            assert_eq!(row.o_wins + row.o_draws + row.o_losses, games, "{:?}", row);
+        }
+        assert_eq!(format!("{:?}", csv_rows), format!("{:?}", jsonl_rows));
+    }
+}
@@ -0,0 +1,275 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! A small multilayer perceptron, trained on the CPU without any ML runtime.
+//!
+//! Hidden layers use ReLU and the output layer is linear. Training fits single outputs to
+//! targets under squared error: gradients of a minibatch are summed into `Gradients` by
+//! `Mlp::accumulate` and applied by `Adam::step`.
+
+use crate::random;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Layer {
+    inputs: usize,
+    /// One row of `inputs` weights per output.
+    weights: Vec<f64>,
+    biases: Vec<f64>,
+}
+
+impl Layer {
+    fn zeros(inputs: usize, outputs: usize) -> Self {
+        Layer { inputs, weights: vec![0.0; inputs * outputs], biases: vec![0.0; outputs] }
+    }
+
+    /// He-uniform weights and zero biases, which suit the ReLUs the layer feeds.
+    fn random(inputs: usize, outputs: usize) -> Self {
+        let mut rng = random::rng();
+        let limit = (6.0 / inputs as f64).sqrt();
+        let mut layer = Layer::zeros(inputs, outputs);
+        layer.weights.iter_mut().for_each(|weight| *weight = rng.random_range(-limit..limit));
+        layer
+    }
+
+    fn outputs(&self) -> usize {
+        self.biases.len()
+    }
+
+    fn forward(&self, input: &[f64]) -> Vec<f64> {
+        self.weights
+            .chunks(self.inputs)
+            .zip(&self.biases)
+            .map(|(row, bias)| bias + dot(row, input))
+            .collect()
+    }
+}
+
+/// A dot product summed in four lanes, which the compiler can vectorise.
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    let mut lanes = [0.0; 4];
+    let (a_chunks, b_chunks) = (a.chunks_exact(4), b.chunks_exact(4));
+    let tail: f64 = a_chunks.remainder().iter().zip(b_chunks.remainder()).map(|(x, y)| x * y).sum();
+    for (x, y) in a_chunks.zip(b_chunks) {
+        for lane in 0..4 {
+            lanes[lane] += x[lane] * y[lane];
+        }
+    }
+    lanes.iter().sum::<f64>() + tail
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mlp {
+    layers: Vec<Layer>,
+}
+
+/// Gradients shaped like the network they were computed for.
+#[derive(Debug, Clone)]
+pub struct Gradients {
+    layers: Vec<Layer>,
+}
+
+impl Mlp {
+    /// A randomly initialised network with the given layer sizes, inputs first and outputs last.
+    pub fn new(sizes: &[usize]) -> Self {
+        assert!(sizes.len() >= 2, "a network needs an input and an output layer");
+        Mlp { layers: sizes.windows(2).map(|pair| Layer::random(pair[0], pair[1])).collect() }
+    }
+
+    /// Checks that the layers fit together, as a deserialized network may not: each must have
+    /// inputs, a row of weights per output, and as many inputs as the previous layer has outputs.
+    pub fn check(&self) -> Result<(), String> {
+        if self.layers.is_empty() {
+            return Err("a network needs at least one layer".to_string());
+        }
+        for (i, layer) in self.layers.iter().enumerate() {
+            if layer.inputs == 0 || layer.weights.len() != layer.inputs * layer.outputs() {
+                return Err(format!("layer {} has {} weights for {} inputs and {} outputs", i + 1, layer.weights.len(), layer.inputs, layer.outputs()));
+            }
+            if i > 0 && layer.inputs != self.layers[i - 1].outputs() {
+                return Err(format!("layer {} takes {} inputs, but the layer before has {} outputs", i + 1, layer.inputs, self.layers[i - 1].outputs()));
+            }
+        }
+        Ok(())
+    }
+
+    /// The layer sizes, inputs first.
+    pub fn sizes(&self) -> Vec<usize> {
+        std::iter::once(self.layers[0].inputs).chain(self.layers.iter().map(Layer::outputs)).collect()
+    }
+
+    pub fn forward(&self, input: &[f64]) -> Vec<f64> {
+        self.activations(input).pop().unwrap()
+    }
+
+    /// The input followed by the output of every layer.
+    fn activations(&self, input: &[f64]) -> Vec<Vec<f64>> {
+        let mut activations = vec![input.to_vec()];
+        for (i, layer) in self.layers.iter().enumerate() {
+            let mut output = layer.forward(activations.last().unwrap());
+            if i + 1 < self.layers.len() {
+                output.iter_mut().for_each(|x| *x = x.max(0.0));
+            }
+            activations.push(output);
+        }
+        activations
+    }
+
+    pub fn zero_gradients(&self) -> Gradients {
+        Gradients { layers: self.layers.iter().map(|layer| Layer::zeros(layer.inputs, layer.outputs())).collect() }
+    }
+
+    /// Adds the gradient of `(output[index] - target)² / 2` to `gradients` and returns the
+    /// error `target - output[index]`.
+    pub fn accumulate(&self, input: &[f64], index: usize, target: f64, gradients: &mut Gradients) -> f64 {
+        let activations = self.activations(input);
+        let error = target - activations.last().unwrap()[index];
+        let mut delta = vec![0.0; self.layers.last().unwrap().outputs()];
+        delta[index] = -error;
+        for (i, layer) in self.layers.iter().enumerate().rev() {
+            let input = &activations[i];
+            let gradient = &mut gradients.layers[i];
+            for (output, &d) in delta.iter().enumerate().filter(|&(_, &d)| d != 0.0) {
+                gradient.biases[output] += d;
+                let row = &mut gradient.weights[output * layer.inputs..(output + 1) * layer.inputs];
+                row.iter_mut().zip(input).for_each(|(g, x)| *g += d * x);
+            }
+            if i > 0 {
+                // back through the weights and the ReLU that produced this layer's input
+                let mut previous = vec![0.0; layer.inputs];
+                for (row, &d) in layer.weights.chunks(layer.inputs).zip(&delta).filter(|&(_, &d)| d != 0.0) {
+                    previous.iter_mut().zip(row).for_each(|(p, weight)| *p += d * weight);
+                }
+                previous.iter_mut().zip(input).filter(|&(_, &x)| x <= 0.0).for_each(|(p, _)| *p = 0.0);
+                delta = previous;
+            }
+        }
+        error
+    }
+}
+
+/// The Adam optimiser, with the usual moment decay rates.
+#[derive(Debug, Clone)]
+pub struct Adam {
+    pub learning_rate: f64,
+    first: Gradients,
+    second: Gradients,
+    steps: i32,
+}
+
+impl Adam {
+    const BETA1: f64 = 0.9;
+    const BETA2: f64 = 0.999;
+    const EPSILON: f64 = 1e-8;
+
+    pub fn new(network: &Mlp, learning_rate: f64) -> Self {
+        Adam { learning_rate, first: network.zero_gradients(), second: network.zero_gradients(), steps: 0 }
+    }
+
+    /// Applies gradients summed over `batch` samples.
+    pub fn step(&mut self, network: &mut Mlp, gradients: &Gradients, batch: usize) {
+        self.steps += 1;
+        let step_size = self.learning_rate * (1.0 - Self::BETA2.powi(self.steps)).sqrt() / (1.0 - Self::BETA1.powi(self.steps));
+        for (i, layer) in network.layers.iter_mut().enumerate() {
+            let (gradient, first, second) = (&gradients.layers[i], &mut self.first.layers[i], &mut self.second.layers[i]);
+            let parameters = layer.weights.iter_mut().chain(layer.biases.iter_mut());
+            let gradient = gradient.weights.iter().chain(&gradient.biases);
+            let first = first.weights.iter_mut().chain(first.biases.iter_mut());
+            let second = second.weights.iter_mut().chain(second.biases.iter_mut());
+            for (((parameter, g), m), v) in parameters.zip(gradient).zip(first).zip(second) {
+                let g = g / batch as f64;
+                *m = Self::BETA1 * *m + (1.0 - Self::BETA1) * g;
+                *v = Self::BETA2 * *v + (1.0 - Self::BETA2) * g * g;
+                *parameter -= step_size * *m / (v.sqrt() + Self::EPSILON);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `p`th parameter of a layer, weights first.
+    fn parameter(layer: &mut Layer, p: usize) -> &mut f64 {
+        let weights = layer.weights.len();
+        if p < weights { &mut layer.weights[p] } else { &mut layer.biases[p - weights] }
+    }
+
+    fn loss(network: &Mlp, input: &[f64], index: usize, target: f64) -> f64 {
+        (network.forward(input)[index] - target).powi(2) / 2.0
+    }
+
+    #[test]
+    fn accumulate_matches_the_numeric_gradient() {
+        const H: f64 = 1e-6;
+        random::seed(50);
+        let mut network = Mlp::new(&[4, 6, 5, 3]);
+        let input = [0.5, -1.0, 0.25, 2.0];
+        let (index, target) = (1, 0.75);
+        let mut gradients = network.zero_gradients();
+        let error = network.accumulate(&input, index, target, &mut gradients);
+        assert!((error - (target - network.forward(&input)[index])).abs() < 1e-12);
+        for i in 0..network.layers.len() {
+            for p in 0..network.layers[i].weights.len() + network.layers[i].biases.len() {
+                let original = *parameter(&mut network.layers[i], p);
+                *parameter(&mut network.layers[i], p) = original + H;
+                let above = loss(&network, &input, index, target);
+                *parameter(&mut network.layers[i], p) = original - H;
+                let below = loss(&network, &input, index, target);
+                *parameter(&mut network.layers[i], p) = original;
+                let (numeric, analytic) = ((above - below) / (2.0 * H), *parameter(&mut gradients.layers[i], p));
+                assert!((numeric - analytic).abs() < 1e-6, "layer {} parameter {}: {} vs {}", i, p, numeric, analytic);
+            }
+        }
+    }
+
+    #[test]
+    fn adam_steps_against_the_gradient() {
+        random::seed(50);
+        let mut network = Mlp::new(&[2, 4, 2]);
+        let input = [1.0, -0.5];
+        let mut before = network.clone();
+        let mut optimizer = Adam::new(&network, 0.01);
+        let mut gradients = network.zero_gradients();
+        network.accumulate(&input, 0, 3.0, &mut gradients);
+        network.accumulate(&input, 0, 3.0, &mut gradients);
+        optimizer.step(&mut network, &gradients, 2);
+        // the first step moves every parameter by the learning rate, whatever its gradient's size
+        for i in 0..network.layers.len() {
+            for p in 0..network.layers[i].weights.len() + network.layers[i].biases.len() {
+                let gradient = *parameter(&mut gradients.layers[i], p);
+                let moved = *parameter(&mut network.layers[i], p) - *parameter(&mut before.layers[i], p);
+                let expected = if gradient == 0.0 { 0.0 } else { -0.01 * gradient.signum() };
+                assert!((moved - expected).abs() < 1e-6, "layer {} parameter {}: moved {}", i, p, moved);
+            }
+        }
+        for _ in 0..500 {
+            let mut gradients = network.zero_gradients();
+            network.accumulate(&input, 0, 3.0, &mut gradients);
+            optimizer.step(&mut network, &gradients, 1);
+        }
+        assert!(loss(&network, &input, 0, 3.0) < 1e-4 * loss(&before, &input, 0, 3.0));
+    }
+
+    #[test]
+    fn mismatched_layers_are_refused() {
+        random::seed(50);
+        let network = Mlp::new(&[3, 4, 2]);
+        assert_eq!(network.check(), Ok(()));
+        assert!(Mlp { layers: Vec::new() }.check().is_err());
+        let mut no_inputs = network.clone();
+        no_inputs.layers[0] = Layer::zeros(0, 4);
+        assert_eq!(no_inputs.check(), Err("layer 1 has 0 weights for 0 inputs and 4 outputs".to_string()));
+        let mut short = network.clone();
+        short.layers[1].weights.pop();
+        assert_eq!(short.check(), Err("layer 2 has 7 weights for 4 inputs and 2 outputs".to_string()));
+        let mut unconnected = network;
+        unconnected.layers[1] = Layer::zeros(5, 2);
+        assert_eq!(unconnected.check(), Err("layer 2 takes 5 inputs, but the layer before has 4 outputs".to_string()));
+    }
+}
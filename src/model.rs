@@ -0,0 +1,519 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! The saved model format.
+//!
+//! A model file is two lines of JSON: a `ModelHeader` describing the model, followed by the
+//! agent itself. Keeping the header on its own line lets it be read without parsing the
+//! Q-table. Version 1 files, written before the header existed, hold only the agent and are
+//! migrated when loaded.
+//!
+//! Models saved to a path ending in `.bin` use the compact binary format of `binary` instead,
+//! which carries the same header. Loading recognises either format by its contents.
+//!
+//! Network agents (`dqn`) are saved the same way under their own algorithm, with the network's
+//! layer sizes in the header. They are always JSON.
+//!
+//! Models are written to a temporary file next to the destination and renamed over it, so an
+//! interrupted save never leaves a truncated model behind.
+
+use crate::convergence::TrainingSummary;
+use crate::dqn::{self, DqnAgent};
+use crate::rules::Rules;
+use crate::{AlphaSchedule, BoardSize, Exploration, QLearningAgent, MIN_EPSILON};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const FORMAT: &str = "q-learning-tictactoe";
+pub const VERSION: u32 = 2;
+pub const ALGORITHM: &str = "tabular-q-learning";
+pub const NETWORK_ALGORITHM: &str = "dqn-mlp";
+/// The extension that selects the binary format when saving.
+pub const BINARY_EXTENSION: &str = "bin";
+
+mod binary;
+
+pub use binary::pack_state;
+
+/// Where an agent came from; kept next to the agent and saved in the model header.
+#[derive(Debug, Clone, Default)]
+pub struct TrainingMetadata {
+    pub seed: Option<u64>,
+    /// Episodes played over all training runs of the agent.
+    pub episodes: usize,
+    /// The exploration rate training started from, before it was decayed.
+    pub initial_epsilon: Option<f64>,
+    pub summary: Option<TrainingSummary>,
+    /// The board the agent was trained on.
+    pub board: BoardSize,
+    pub rules: Rules,
+}
+
+impl TrainingMetadata {
+    /// Fails for agents trained under rule variants, whose Q-tables standard games cannot use.
+    pub fn check_standard_rules(&self) -> Result<(), String> {
+        match self.rules {
+            Rules::Standard => Ok(()),
+            rules => Err(format!("trained for {} rules, not the standard ones", rules)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hyperparameters {
+    pub alpha: f64,
+    pub gamma: f64,
+    pub epsilon: f64,
+    pub min_epsilon: f64,
+    pub alpha_schedule: AlphaSchedule,
+    pub exploration: Exploration,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelHeader {
+    pub format: String,
+    pub version: u32,
+    pub algorithm: String,
+    pub hyperparameters: Hyperparameters,
+    pub episodes: usize,
+    pub seed: Option<u64>,
+    /// Seconds since the Unix epoch.
+    pub created: u64,
+    pub states: usize,
+    pub summary: Option<TrainingSummary>,
+    /// Missing from models saved before other board sizes existed, which are all 3×3.
+    #[serde(default)]
+    pub board: BoardSize,
+    /// Missing from models saved before rule variants existed, which all play standard rules.
+    #[serde(default)]
+    pub rules: Rules,
+    /// The layer sizes of a network agent, inputs first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layers: Option<Vec<usize>>,
+}
+
+impl ModelHeader {
+    pub(crate) fn describe(agent: &QLearningAgent, version: u32, created: u64) -> Self {
+        ModelHeader {
+            format: FORMAT.to_string(),
+            version,
+            algorithm: ALGORITHM.to_string(),
+            hyperparameters: Hyperparameters {
+                alpha: agent.alpha,
+                gamma: agent.gamma,
+                epsilon: agent.metadata.initial_epsilon.unwrap_or(agent.epsilon),
+                min_epsilon: MIN_EPSILON,
+                alpha_schedule: agent.alpha_schedule,
+                exploration: agent.exploration,
+            },
+            episodes: agent.metadata.episodes,
+            seed: agent.metadata.seed,
+            created,
+            states: agent.q_table.len(),
+            summary: agent.metadata.summary.clone(),
+            board: agent.metadata.board,
+            rules: agent.metadata.rules,
+            layers: None,
+        }
+    }
+
+    fn describe_network(agent: &DqnAgent, created: u64) -> Self {
+        ModelHeader {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            algorithm: NETWORK_ALGORITHM.to_string(),
+            hyperparameters: Hyperparameters {
+                alpha: agent.learning_rate,
+                gamma: agent.gamma,
+                epsilon: agent.metadata.initial_epsilon.unwrap_or(agent.epsilon),
+                min_epsilon: MIN_EPSILON,
+                alpha_schedule: AlphaSchedule::Constant,
+                exploration: Exploration::EpsilonGreedy,
+            },
+            episodes: agent.metadata.episodes,
+            seed: agent.metadata.seed,
+            created,
+            states: 0,
+            summary: agent.metadata.summary.clone(),
+            board: agent.metadata.board,
+            rules: agent.metadata.rules,
+            layers: Some(agent.network.sizes()),
+        }
+    }
+
+    /// Rejects headers this build cannot read.
+    fn check(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.format != FORMAT {
+            return Err(format!("not a {} model (format '{}')", FORMAT, self.format).into());
+        }
+        if self.version > VERSION {
+            return Err(format!(
+                "model file version {} is newer than the supported version {}; update {} to load it",
+                self.version, VERSION, FORMAT
+            )
+            .into());
+        }
+        if self.algorithm != ALGORITHM && self.algorithm != NETWORK_ALGORITHM {
+            return Err(format!("model holds an unknown '{}' agent", self.algorithm).into());
+        }
+        Ok(())
+    }
+
+    /// Rejects headers this build cannot read or that hold another kind of agent.
+    fn expect(&self, algorithm: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.check()?;
+        if self.algorithm != algorithm {
+            return Err(format!("model holds a '{}' agent, not {}", self.algorithm, algorithm).into());
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ModelHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hyperparameters = &self.hyperparameters;
+        writeln!(f, "Format: {} v{}", self.format, self.version)?;
+        writeln!(f, "Algorithm: {}", self.algorithm)?;
+        writeln!(f, "Board: {}x{}, {} in a row", self.board.width, self.board.height, self.board.k)?;
+        writeln!(f, "Rules: {}", self.rules)?;
+        if self.created > 0 {
+            writeln!(f, "Created: {}", format_timestamp(self.created))?;
+        }
+        writeln!(f, "Episodes: {}", self.episodes)?;
+        match self.seed {
+            Some(seed) => writeln!(f, "Seed: {}", seed)?,
+            None => writeln!(f, "Seed: unknown")?,
+        }
+        match &self.layers {
+            Some(layers) => {
+                let shape: Vec<String> = layers.iter().map(usize::to_string).collect();
+                let parameters: usize = layers.windows(2).map(|pair| (pair[0] + 1) * pair[1]).sum();
+                writeln!(f, "Network: {} ({} parameters)", shape.join("-"), parameters)?
+            }
+            None => writeln!(f, "States: {}", self.states)?,
+        }
+        writeln!(
+            f,
+            "Hyperparameters: alpha {}, gamma {}, epsilon {} decaying to {}, alpha schedule {:?}, exploration {:?}",
+            hyperparameters.alpha,
+            hyperparameters.gamma,
+            hyperparameters.epsilon,
+            hyperparameters.min_epsilon,
+            hyperparameters.alpha_schedule,
+            hyperparameters.exploration
+        )?;
+        if let Some(summary) = &self.summary {
+            writeln!(f, "{}", summary)?;
+        }
+        Ok(())
+    }
+}
+
+/// Saves in the binary format if `path` ends in `.bin` and as JSON otherwise.
+pub fn save(agent: &QLearningAgent, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let created = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+    let header = ModelHeader::describe(agent, VERSION, created);
+    let bytes = if is_binary_path(path) {
+        binary::encode(&header, agent).map_err(|err| format!("{}: {}", path, err))?
+    } else {
+        format!("{}\n{}\n", serde_json::to_string(&header)?, serde_json::to_string(agent)?).into_bytes()
+    };
+    write_atomically(Path::new(path), &bytes).map_err(|err| format!("{}: {}", path, err))?;
+    Ok(())
+}
+
+/// Writes to a temporary file in the same directory, then renames it over `path`.
+fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(format!(".{}.tmp", std::process::id()));
+    let temporary = PathBuf::from(temporary);
+    let result = File::create(&temporary).and_then(|mut file| {
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&temporary, path)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary);
+    }
+    result
+}
+
+pub fn load(path: &str) -> Result<QLearningAgent, Box<dyn std::error::Error>> {
+    let contents = fs::read(path)?;
+    if contents.starts_with(binary::MAGIC) {
+        let (header, agent) = binary::decode(&contents).map_err(|err| format!("{}: {}", path, err))?;
+        header.expect(ALGORITHM).map_err(|err| format!("{}: {}", path, err))?;
+        return Ok(with_metadata(agent, header));
+    }
+    let contents = String::from_utf8(contents).map_err(|err| format!("{}: {}", path, err))?;
+    let (first_line, rest) = contents.split_once('\n').unwrap_or((&contents, ""));
+    let first: Value = serde_json::from_str(first_line).map_err(|err| format!("{}: {}", path, err))?;
+    if first.get("format").is_none() {
+        return migrate_v1(first).map_err(|err| format!("{}: version 1 model: {}", path, err).into());
+    }
+    let header: ModelHeader = serde_json::from_value(first).map_err(|err| format!("{}: bad header: {}", path, err))?;
+    header.expect(ALGORITHM).map_err(|err| format!("{}: {}", path, err))?;
+    let agent: QLearningAgent = serde_json::from_str(rest).map_err(|err| format!("{}: {}", path, err))?;
+    Ok(with_metadata(agent, header))
+}
+
+/// Saves a network agent as JSON.
+pub fn save_network(agent: &DqnAgent, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if is_binary_path(path) {
+        return Err(format!("{}: the binary format only holds tabular agents", path).into());
+    }
+    let created = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+    let header = ModelHeader::describe_network(agent, created);
+    let bytes = format!("{}\n{}\n", serde_json::to_string(&header)?, serde_json::to_string(agent)?).into_bytes();
+    write_atomically(Path::new(path), &bytes).map_err(|err| format!("{}: {}", path, err))?;
+    Ok(())
+}
+
+pub fn load_network(path: &str) -> Result<DqnAgent, Box<dyn std::error::Error>> {
+    let contents = fs::read_to_string(path)?;
+    let (first_line, rest) = contents.split_once('\n').unwrap_or((&contents, ""));
+    let header: ModelHeader = serde_json::from_str(first_line).map_err(|err| format!("{}: bad header: {}", path, err))?;
+    header.expect(NETWORK_ALGORITHM).map_err(|err| format!("{}: {}", path, err))?;
+    let mut agent: DqnAgent = serde_json::from_str(rest).map_err(|err| format!("{}: {}", path, err))?;
+    agent.network.check().map_err(|err| format!("{}: {}", path, err))?;
+    let sizes = agent.network.sizes();
+    if sizes.first() != Some(&dqn::input_size(header.board)) || sizes.last() != Some(&header.board.cells()) {
+        return Err(format!("{}: a network with layers {:?} does not fit {}", path, sizes, header.board).into());
+    }
+    agent.metadata = metadata(header);
+    Ok(agent)
+}
+
+fn is_binary_path(path: &str) -> bool {
+    Path::new(path).extension().is_some_and(|extension| extension == BINARY_EXTENSION)
+}
+
+fn with_metadata(mut agent: QLearningAgent, header: ModelHeader) -> QLearningAgent {
+    agent.metadata = metadata(header);
+    agent
+}
+
+fn metadata(header: ModelHeader) -> TrainingMetadata {
+    TrainingMetadata {
+        seed: header.seed,
+        episodes: header.episodes,
+        initial_epsilon: Some(header.hyperparameters.epsilon),
+        summary: header.summary,
+        board: header.board,
+        rules: header.rules,
+    }
+}
+
+/// Reads only the header of a model. Version 1 files have none, so one is derived from the agent.
+pub fn read_header(path: &str) -> Result<ModelHeader, Box<dyn std::error::Error>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    if reader.fill_buf()?.starts_with(binary::MAGIC) {
+        let header = binary::read_header(&mut reader).map_err(|err| format!("{}: {}", path, err))?;
+        header.check().map_err(|err| format!("{}: {}", path, err))?;
+        return Ok(header);
+    }
+    let mut first_line = String::new();
+    reader.read_line(&mut first_line)?;
+    let first: Value = serde_json::from_str(&first_line).map_err(|err| format!("{}: {}", path, err))?;
+    if first.get("format").is_none() {
+        let agent = migrate_v1(first).map_err(|err| format!("{}: version 1 model: {}", path, err))?;
+        return Ok(ModelHeader::describe(&agent, 1, 0));
+    }
+    let header: ModelHeader = serde_json::from_value(first).map_err(|err| format!("{}: bad header: {}", path, err))?;
+    header.check().map_err(|err| format!("{}: {}", path, err))?;
+    Ok(header)
+}
+
+/// Version 1 files are a bare agent, which may carry the summary of its training run.
+fn migrate_v1(mut agent: Value) -> Result<QLearningAgent, Box<dyn std::error::Error>> {
+    let summary = agent.as_object_mut().and_then(|fields| fields.remove("summary"));
+    let mut agent: QLearningAgent = serde_json::from_value(agent)?;
+    if let Some(summary) = summary.filter(|summary| !summary.is_null()) {
+        agent.metadata.summary = Some(serde_json::from_value(summary)?);
+    }
+    Ok(agent)
+}
+
+/// Formats seconds since the Unix epoch as a UTC date and time.
+fn format_timestamp(seconds: u64) -> String {
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+    // civil date from days since 1970-01-01, after Howard Hinnant's algorithm
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{random, train_q_learning, Board, TrainingOptions};
+    use std::path::PathBuf;
+
+    /// A directory of its own for a test's files.
+    fn test_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("q-learning-tictactoe-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    fn path(directory: &Path, file: &str) -> String {
+        directory.join(file).to_string_lossy().into_owned()
+    }
+
+    fn trained_agent() -> QLearningAgent {
+        let mut agent = QLearningAgent::new(0.2, 0.8, 0.7);
+        let mut options = TrainingOptions { seed: Some(34), output: None, ..TrainingOptions::new(300) };
+        train_q_learning(&mut agent, &mut options);
+        agent.alpha_schedule = AlphaSchedule::InverseVisits;
+        agent.exploration = Exploration::Ucb { c: 1.25 };
+        agent
+    }
+
+    /// Rewrites the header of the JSON model at `path`.
+    fn edit_header(path: &str, edit: impl FnOnce(&mut Value)) {
+        let contents = fs::read_to_string(path).unwrap();
+        let (header, agent) = contents.split_once('\n').unwrap();
+        let mut header: Value = serde_json::from_str(header).unwrap();
+        edit(&mut header);
+        fs::write(path, format!("{}\n{}", header, agent)).unwrap();
+    }
+
+    #[test]
+    fn version_1_models_are_migrated() {
+        let directory = test_directory("migrate");
+        let path = path(&directory, "v1.json");
+        let agent = trained_agent();
+        fs::write(&path, serde_json::to_string(&agent).unwrap()).unwrap();
+        let loaded = load(&path).unwrap();
+        assert_eq!((loaded.alpha, loaded.gamma, loaded.q_table.len()), (agent.alpha, agent.gamma, agent.q_table.len()));
+        assert_eq!(loaded.alpha_schedule, AlphaSchedule::InverseVisits);
+        let header = read_header(&path).unwrap();
+        assert_eq!((header.version, header.algorithm.as_str(), header.states), (1, ALGORITHM, agent.q_table.len()));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn newer_versions_and_other_algorithms_are_refused() {
+        let directory = test_directory("refuse");
+        let (tabular, network) = (path(&directory, "agent.json"), path(&directory, "network.json"));
+        save(&QLearningAgent::new(0.1, 0.9, 0.5), &tabular).unwrap();
+        save_network(&DqnAgent::new(BoardSize::CLASSIC, &[4]), &network).unwrap();
+        assert!(load(&network).unwrap_err().to_string().contains("holds a 'dqn-mlp' agent"));
+        assert!(load_network(&tabular).unwrap_err().to_string().contains("holds a 'tabular-q-learning' agent"));
+
+        edit_header(&tabular, |header| header["version"] = (VERSION + 1).into());
+        assert!(load(&tabular).unwrap_err().to_string().contains("is newer than the supported version"));
+        assert!(read_header(&tabular).is_err());
+        edit_header(&tabular, |header| {
+            header["version"] = VERSION.into();
+            header["algorithm"] = "policy-gradient".into();
+        });
+        assert!(load(&tabular).unwrap_err().to_string().contains("unknown 'policy-gradient' agent"));
+        assert!(read_header(&tabular).is_err());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn headers_are_read_without_the_table() {
+        let directory = test_directory("header");
+        let agent = trained_agent();
+        for file in ["agent.json", "agent.bin"] {
+            let path = path(&directory, file);
+            save(&agent, &path).unwrap();
+            // cut the table short, which only loading the agent notices
+            let contents = fs::read(&path).unwrap();
+            fs::write(&path, &contents[..contents.len() - 100]).unwrap();
+            assert!(load(&path).is_err());
+            let header = read_header(&path).unwrap();
+            assert_eq!((header.version, header.states, header.episodes), (VERSION, agent.q_table.len(), 300));
+            assert_eq!(header.hyperparameters.exploration, Exploration::Ucb { c: 1.25 });
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn json_and_binary_models_load_the_same_agent() {
+        let directory = test_directory("formats");
+        let agent = trained_agent();
+        save(&agent, &path(&directory, "agent.json")).unwrap();
+        save(&agent, &path(&directory, "agent.bin")).unwrap();
+        let loaded = [load(&path(&directory, "agent.json")).unwrap(), load(&path(&directory, "agent.bin")).unwrap()];
+        for loaded in &loaded {
+            assert_eq!((loaded.alpha, loaded.gamma, loaded.epsilon, loaded.episode), (agent.alpha, agent.gamma, agent.epsilon, agent.episode));
+            assert_eq!((loaded.alpha_schedule, loaded.exploration), (AlphaSchedule::InverseVisits, Exploration::Ucb { c: 1.25 }));
+            assert_eq!((loaded.metadata.seed, loaded.metadata.episodes), (Some(34), 300));
+            assert_eq!(loaded.q_table.len(), agent.q_table.len());
+            for (state, actions) in &agent.q_table {
+                assert_eq!(loaded.q_table[state].len(), actions.len());
+                for (action, entry) in actions {
+                    let other = loaded.q_table[state][action];
+                    assert_eq!(
+                        (other.value, other.visits, other.last_episode, other.td_error),
+                        (entry.value, entry.visits, entry.last_episode, entry.td_error)
+                    );
+                }
+            }
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn networks_survive_saving_and_loading() {
+        random::seed(50);
+        let directory = test_directory("network");
+        let path = path(&directory, "network.json");
+        let mut agent = DqnAgent::new(BoardSize::CLASSIC, &[16, 8]);
+        agent.gamma = 0.8;
+        agent.batch_size = 7;
+        agent.metadata = TrainingMetadata { seed: Some(50), episodes: 1234, initial_epsilon: Some(0.6), ..agent.metadata };
+        save_network(&agent, &path).unwrap();
+
+        let loaded = load_network(&path).unwrap();
+        assert_eq!(loaded.network.sizes(), [29, 16, 8, 9]);
+        assert_eq!((loaded.gamma, loaded.batch_size, loaded.epsilon), (0.8, 7, agent.epsilon));
+        assert_eq!((loaded.metadata.seed, loaded.metadata.episodes), (Some(50), 1234));
+        assert_eq!((loaded.metadata.initial_epsilon, loaded.metadata.board), (Some(0.6), BoardSize::CLASSIC));
+        assert!(!loaded.train);
+        let input = DqnAgent::encode(&Board::from_state("X---O----").unwrap());
+        assert_eq!(loaded.network.forward(&input), agent.network.forward(&input));
+
+        // nor are layers whose weights do not fit their sizes
+        let contents = fs::read_to_string(&path).unwrap();
+        let (header, rest) = contents.split_once('\n').unwrap();
+        let mut body: serde_json::Value = serde_json::from_str(rest).unwrap();
+        body["network"]["layers"][1]["weights"].as_array_mut().unwrap().pop();
+        fs::write(&path, format!("{}\n{}", header, body)).unwrap();
+        assert!(load_network(&path).unwrap_err().to_string().contains("layer 2 has 127 weights"));
+
+        // a header for another board does not fit the network
+        agent.metadata.board = BoardSize::parse("4x4:3").unwrap();
+        save_network(&agent, &path).unwrap();
+        assert!(load_network(&path).is_err());
+        assert!(save_network(&agent, &directory.join("network.bin").to_string_lossy()).is_err());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,247 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! The compact binary model format.
+//!
+//! All numbers are little-endian. A file starts with the magic bytes, the length of the JSON
+//! `ModelHeader` and the header itself, so it can be inspected without reading the table.
+//! The agent follows as fixed-size fields: alpha, gamma and epsilon as `f64`, the episode
+//! counter as `u64`, the alpha schedule and exploration as a tag byte each (UCB followed by
+//! its `c`), then the number of states as `u32`. Each state is its board packed into a base-3
+//! `u16` and an action count byte, and each action its cell index byte and the entry's
+//! value, visits, last episode and TD error in 8 bytes each.
+
+use super::ModelHeader;
+use crate::rules::Rules;
+use crate::{AlphaSchedule, BoardSize, Exploration, QEntry, QLearningAgent};
+use std::collections::HashMap;
+use std::io::Read;
+
+pub const MAGIC: &[u8; 4] = b"QTTB";
+
+/// Encodes an agent, failing on states or actions that are not tic-tac-toe positions and moves.
+pub fn encode(header: &ModelHeader, agent: &QLearningAgent) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    if header.board != BoardSize::CLASSIC {
+        return Err(format!("the binary format only holds 3x3 models, not {}", header.board).into());
+    }
+    if header.rules != Rules::Standard {
+        return Err(format!("the binary format only holds models of the standard rules, not {}", header.rules).into());
+    }
+    let header = serde_json::to_vec(header)?;
+    let entries: usize = agent.q_table.values().map(HashMap::len).sum();
+    let mut bytes = Vec::with_capacity(64 + header.len() + 3 * agent.q_table.len() + 33 * entries);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&(header.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&header);
+
+    for value in [agent.alpha, agent.gamma, agent.epsilon] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes.extend_from_slice(&(agent.episode as u64).to_le_bytes());
+    bytes.push(match agent.alpha_schedule {
+        AlphaSchedule::Constant => 0,
+        AlphaSchedule::InverseVisits => 1,
+    });
+    match agent.exploration {
+        Exploration::EpsilonGreedy => bytes.push(0),
+        Exploration::Ucb { c } => {
+            bytes.push(1);
+            bytes.extend_from_slice(&c.to_le_bytes());
+        }
+    }
+
+    bytes.extend_from_slice(&(agent.q_table.len() as u32).to_le_bytes());
+    for (state, actions) in &agent.q_table {
+        bytes.extend_from_slice(&pack_state(state)?.to_le_bytes());
+        bytes.push(actions.len() as u8);
+        for (action, entry) in actions {
+            bytes.push(pack_action(action)?);
+            bytes.extend_from_slice(&entry.value.to_le_bytes());
+            bytes.extend_from_slice(&entry.visits.to_le_bytes());
+            bytes.extend_from_slice(&(entry.last_episode as u64).to_le_bytes());
+            bytes.extend_from_slice(&entry.td_error.to_le_bytes());
+        }
+    }
+    Ok(bytes)
+}
+
+/// Decodes a whole file into its header and agent.
+pub fn decode(bytes: &[u8]) -> Result<(ModelHeader, QLearningAgent), Box<dyn std::error::Error>> {
+    let mut reader = bytes;
+    let header = read_header(&mut reader)?;
+    let alpha = f64::from_le_bytes(take(&mut reader)?);
+    let gamma = f64::from_le_bytes(take(&mut reader)?);
+    let epsilon = f64::from_le_bytes(take(&mut reader)?);
+    let mut agent = QLearningAgent::new(alpha, gamma, epsilon);
+    agent.episode = u64::from_le_bytes(take(&mut reader)?) as usize;
+    agent.alpha_schedule = match take::<1>(&mut reader)?[0] {
+        0 => AlphaSchedule::Constant,
+        1 => AlphaSchedule::InverseVisits,
+        tag => return Err(format!("unknown alpha schedule tag {}", tag).into()),
+    };
+    agent.exploration = match take::<1>(&mut reader)?[0] {
+        0 => Exploration::EpsilonGreedy,
+        1 => Exploration::Ucb { c: f64::from_le_bytes(take(&mut reader)?) },
+        tag => return Err(format!("unknown exploration tag {}", tag).into()),
+    };
+
+    let states = u32::from_le_bytes(take(&mut reader)?) as usize;
+    agent.q_table.reserve(states);
+    for _ in 0..states {
+        let state = unpack_state(u16::from_le_bytes(take(&mut reader)?))?;
+        let count = take::<1>(&mut reader)?[0] as usize;
+        let mut actions = HashMap::with_capacity(count);
+        for _ in 0..count {
+            let action = unpack_action(take::<1>(&mut reader)?[0])?;
+            let entry = QEntry {
+                value: f64::from_le_bytes(take(&mut reader)?),
+                visits: u64::from_le_bytes(take(&mut reader)?),
+                last_episode: u64::from_le_bytes(take(&mut reader)?) as usize,
+                td_error: f64::from_le_bytes(take(&mut reader)?),
+            };
+            actions.insert(action, entry);
+        }
+        agent.q_table.insert(state, actions);
+    }
+    if !reader.is_empty() {
+        return Err(format!("{} unexpected bytes after the Q-table", reader.len()).into());
+    }
+    Ok((header, agent))
+}
+
+/// Reads the magic bytes and the header, leaving `reader` at the start of the agent.
+pub fn read_header(reader: &mut impl Read) -> Result<ModelHeader, Box<dyn std::error::Error>> {
+    if &take::<4>(reader)? != MAGIC {
+        return Err("not a binary model".into());
+    }
+    let length = u32::from_le_bytes(take(reader)?) as usize;
+    let mut header = vec![0; length];
+    reader.read_exact(&mut header).map_err(|_| "truncated model header")?;
+    Ok(serde_json::from_slice(&header)?)
+}
+
+fn take<const N: usize>(reader: &mut impl Read) -> Result<[u8; N], Box<dyn std::error::Error>> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes).map_err(|_| "truncated binary model")?;
+    Ok(bytes)
+}
+
+/// Packs a board state into base 3, with `-`, `X` and `O` as the digits 0, 1 and 2.
+pub fn pack_state(state: &str) -> Result<u16, Box<dyn std::error::Error>> {
+    if state.len() != 9 {
+        return Err(format!("cannot encode state '{}'", state).into());
+    }
+    state.chars().try_fold(0u16, |packed, c| {
+        let digit = match c {
+            '-' => 0,
+            'X' => 1,
+            'O' => 2,
+            _ => return Err(format!("cannot encode state '{}'", state).into()),
+        };
+        Ok(packed * 3 + digit)
+    })
+}
+
+fn unpack_state(mut packed: u16) -> Result<String, Box<dyn std::error::Error>> {
+    if packed >= 3u16.pow(9) {
+        return Err(format!("bad packed state {}", packed).into());
+    }
+    let mut cells = ['-'; 9];
+    for cell in cells.iter_mut().rev() {
+        *cell = ['-', 'X', 'O'][(packed % 3) as usize];
+        packed /= 3;
+    }
+    Ok(cells.iter().collect())
+}
+
+/// Packs a `row,col` action into its cell index.
+fn pack_action(action: &str) -> Result<u8, Box<dyn std::error::Error>> {
+    match action.split_once(',').map(|(row, col)| (row.parse::<u8>(), col.parse::<u8>())) {
+        Some((Ok(row), Ok(col))) if row < 3 && col < 3 && action == format!("{},{}", row, col) => Ok(row * 3 + col),
+        _ => Err(format!("cannot encode action '{}'", action).into()),
+    }
+}
+
+fn unpack_action(index: u8) -> Result<String, Box<dyn std::error::Error>> {
+    if index >= 9 {
+        return Err(format!("bad action index {}", index).into());
+    }
+    Ok(format!("{},{}", index / 3, index % 3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded() -> (ModelHeader, Vec<u8>) {
+        let mut agent = QLearningAgent::new(0.1, 0.9, 0.5);
+        let entry = QEntry { value: 0.25, visits: 3, last_episode: 7, td_error: 0.01 };
+        agent.q_table.entry("X---O----".to_string()).or_default().insert("2,2".to_string(), entry);
+        let header = ModelHeader::describe(&agent, crate::model::VERSION, 0);
+        let bytes = encode(&header, &agent).unwrap();
+        (header, bytes)
+    }
+
+    /// Where the alpha schedule tag is, after the magic, header and the four 8-byte fields.
+    fn schedule_tag(bytes: &[u8]) -> usize {
+        8 + u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize + 32
+    }
+
+    fn error(bytes: &[u8]) -> String {
+        decode(bytes).map(|_| ()).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn packed_states_and_actions_round_trip() {
+        for state in ["---------", "XOXOXOXOX", "O-------X"] {
+            assert_eq!(unpack_state(pack_state(state).unwrap()).unwrap(), state);
+        }
+        for index in 0..9 {
+            assert_eq!(pack_action(&unpack_action(index).unwrap()).unwrap(), index);
+        }
+        assert!(pack_state("X--------O").is_err() && pack_state("X-------A").is_err());
+        assert!(pack_action("3,0").is_err() && pack_action("01,1").is_err() && pack_action("1").is_err());
+        assert!(unpack_state(3u16.pow(9)).is_err() && unpack_action(9).is_err());
+    }
+
+    #[test]
+    fn truncated_files_are_refused() {
+        let (_, bytes) = encoded();
+        assert!(decode(&bytes).is_ok());
+        for length in [2, 6, 10, schedule_tag(&bytes), bytes.len() - 1] {
+            assert!(decode(&bytes[..length]).is_err(), "decoded {} of {} bytes", length, bytes.len());
+        }
+        assert_eq!(error(&bytes[..bytes.len() - 1]), "truncated binary model");
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert_eq!(error(&longer), "1 unexpected bytes after the Q-table");
+    }
+
+    #[test]
+    fn bad_magic_and_tags_are_refused() {
+        let (_, bytes) = encoded();
+        let tag = schedule_tag(&bytes);
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(error(&bad_magic), "not a binary model");
+        let mut bad_schedule = bytes.clone();
+        bad_schedule[tag] = 7;
+        assert_eq!(error(&bad_schedule), "unknown alpha schedule tag 7");
+        let mut bad_exploration = bytes.clone();
+        bad_exploration[tag + 1] = 9;
+        assert_eq!(error(&bad_exploration), "unknown exploration tag 9");
+    }
+
+    #[test]
+    fn only_standard_3x3_models_are_encoded() {
+        let (header, _) = encoded();
+        let agent = QLearningAgent::new(0.1, 0.9, 0.5);
+        let larger = ModelHeader { board: BoardSize::parse("4x4:3").unwrap(), ..header.clone() };
+        assert!(encode(&larger, &agent).is_err());
+        let misere = ModelHeader { rules: Rules::Misere, ..header };
+        assert!(encode(&misere, &agent).is_err());
+    }
+}
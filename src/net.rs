@@ -0,0 +1,293 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! Two-player games over TCP.
+//!
+//! The host owns the board and plays X; the guest plays O. Messages are JSON objects, one per
+//! line. The guest only asks for moves and new games; the host checks each one against its
+//! board and replies with the resulting `State`, which it also sends whenever a guest
+//! connects. A guest that loses the connection keeps trying to reconnect, and the host keeps
+//! the game until it does, so either side can drop out and carry on where it left off.
+//! The board size is not negotiated: both sides must play on the same one.
+
+use crate::{Board, BoardSize, Cell};
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:7878";
+/// The side each end of a connection plays.
+pub const HOST_SIDE: Cell = Cell::X;
+pub const GUEST_SIDE: Cell = Cell::O;
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const ACCEPT_POLL: Duration = Duration::from_millis(100);
+/// How long a send waits on a peer that has stopped reading before giving up on the message.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NetMessage {
+    /// Guest to host: play a move.
+    Move { row: usize, col: usize },
+    /// Guest to host: start a new game.
+    NewGame,
+    /// Host to guest: the position after every change.
+    State { board: String, to_move: String, game_over: bool, winner: Option<String> },
+    /// Host to guest: why a request was refused.
+    Error { message: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetEvent {
+    /// A peer connected, from the given address.
+    Connected(String),
+    Disconnected,
+    Received(NetMessage),
+    /// A problem that does not end the connection, such as a failed reconnect or bad message.
+    Failed(String),
+}
+
+/// The current peer, numbered so a reader thread can tell whether it has been replaced.
+type Peer = Arc<Mutex<(u64, Option<TcpStream>)>>;
+
+/// One end of a networked game. Events from the background threads are collected with `poll`.
+pub struct Connection {
+    events: Receiver<NetEvent>,
+    peer: Peer,
+    closed: Arc<AtomicBool>,
+    /// The address a host listens on.
+    listening: Option<SocketAddr>,
+}
+
+impl Connection {
+    /// Listens on `address`. A guest connecting while another is connected takes its place.
+    pub fn host(address: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let (sender, events) = mpsc::channel();
+        let listening = Some(listener.local_addr()?);
+        let connection = Connection { events, peer: Arc::new(Mutex::new((0, None))), closed: Arc::default(), listening };
+        let (peer, closed) = (connection.peer.clone(), connection.closed.clone());
+        thread::spawn(move || {
+            while !closed.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, address)) => {
+                        if stream.set_nonblocking(false).is_err() {
+                            continue;
+                        }
+                        serve(stream, address.to_string(), &peer, &sender, false);
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL),
+                    Err(err) => {
+                        let _ = sender.send(NetEvent::Failed(err.to_string()));
+                        thread::sleep(ACCEPT_POLL);
+                    }
+                }
+            }
+        });
+        Ok(connection)
+    }
+
+    /// Connects to the host at `address`, retrying until it answers and whenever the
+    /// connection drops.
+    pub fn join(address: &str) -> Self {
+        let address = address.to_string();
+        let (sender, events) = mpsc::channel();
+        let connection = Connection { events, peer: Arc::new(Mutex::new((0, None))), closed: Arc::default(), listening: None };
+        let (peer, closed) = (connection.peer.clone(), connection.closed.clone());
+        thread::spawn(move || {
+            let mut reported = false;
+            while !closed.load(Ordering::Relaxed) {
+                match TcpStream::connect(&address) {
+                    Ok(stream) => {
+                        reported = false;
+                        serve(stream, address.clone(), &peer, &sender, true);
+                    }
+                    // report the first failure of a series rather than one every second
+                    Err(err) if !reported => {
+                        reported = true;
+                        let _ = sender.send(NetEvent::Failed(format!("cannot reach {}: {}", address, err)));
+                    }
+                    Err(_) => {}
+                }
+                thread::sleep(RECONNECT_DELAY);
+            }
+        });
+        connection
+    }
+
+    /// Sends to the peer, returning whether one is connected and took the message. The write
+    /// goes to a handle of its own, so a stalled peer never holds up the threads sharing `peer`.
+    pub fn send(&self, message: &NetMessage) -> bool {
+        let stream = self.peer.lock().unwrap().1.as_ref().map(TcpStream::try_clone);
+        let Some(Ok(mut stream)) = stream else { return false };
+        let line = serde_json::to_string(message).expect("messages serialize");
+        writeln!(stream, "{}", line).and_then(|()| stream.flush()).is_ok()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.peer.lock().unwrap().1.is_some()
+    }
+
+    /// The address a host listens on, with the port chosen when it was given as 0.
+    pub fn local_address(&self) -> Option<SocketAddr> {
+        self.listening
+    }
+
+    pub fn poll(&self) -> Vec<NetEvent> {
+        self.events.try_iter().collect()
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Relaxed);
+        if let Some(stream) = self.peer.lock().unwrap().1.take() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// Makes `stream` the current peer, reports it as connected from `address` and forwards its
+/// messages until it closes. The host reads each guest on its own thread so it can accept a
+/// replacement meanwhile; the guest has nothing else to do and reads on the calling thread.
+fn serve(stream: TcpStream, address: String, peer: &Peer, sender: &Sender<NetEvent>, blocking: bool) {
+    if stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_err() {
+        return;
+    }
+    let Ok(reader) = stream.try_clone() else { return };
+    let id = {
+        let mut peer = peer.lock().unwrap();
+        if let Some(previous) = peer.1.replace(stream) {
+            let _ = previous.shutdown(Shutdown::Both);
+        }
+        peer.0 += 1;
+        peer.0
+    };
+    // only now can a `send` in answer to the event reach the new peer
+    let _ = sender.send(NetEvent::Connected(address));
+    let (peer, sender) = (peer.clone(), sender.clone());
+    let read = move || {
+        for line in BufReader::new(reader).lines() {
+            let Ok(line) = line else { break };
+            let event = match serde_json::from_str(&line) {
+                Ok(message) => NetEvent::Received(message),
+                Err(err) => NetEvent::Failed(format!("bad message '{}': {}", line, err)),
+            };
+            if sender.send(event).is_err() {
+                return;
+            }
+        }
+        let mut peer = peer.lock().unwrap();
+        if peer.0 == id {
+            peer.1 = None;
+            let _ = sender.send(NetEvent::Disconnected);
+        }
+    };
+    if blocking {
+        read();
+    } else {
+        thread::spawn(read);
+    }
+}
+
+/// Plays a move for `side`, refusing it if it is not that side's turn or the cell is taken.
+pub fn play_move(board: &mut Board, side: Cell, row: usize, col: usize) -> Result<(), String> {
+    if board.is_game_over().0 {
+        return Err("the game is over".to_string());
+    }
+    if board.get_current_player().marker != side {
+        return Err(format!("it is not {}'s turn", side));
+    }
+    if !board.make_move(row, col).0.move_successful {
+        return Err(format!("{},{} is not a legal move", row, col));
+    }
+    Ok(())
+}
+
+/// The host's view of the game, as sent to the guest.
+pub fn state_message(board: &Board) -> NetMessage {
+    let (game_over, winner) = board.is_game_over();
+    NetMessage::State {
+        board: board.board_state(),
+        to_move: board.get_current_player().marker.to_string(),
+        game_over,
+        winner: winner.map(|winner| winner.to_string()),
+    }
+}
+
+/// Rebuilds the host's board from a `State` message, if it is a board of `size`.
+pub fn board_from_state(size: BoardSize, board: &str, to_move: &str) -> Option<Board> {
+    let mut game = Board::from_state_with_size(size, board)?;
+    game.set_current_player(match to_move {
+        "X" => Cell::X,
+        "O" => Cell::O,
+        _ => return None,
+    });
+    Some(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// Polls `connection` until an event matches `wanted`, failing after a few seconds.
+    fn wait_for(connection: &Connection, wanted: impl Fn(&NetEvent) -> bool) -> NetEvent {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if let Some(event) = connection.poll().into_iter().find(&wanted) {
+                return event;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("no matching event");
+    }
+
+    #[test]
+    fn host_and_guest_exchange_moves_and_states() {
+        let host = Connection::host("127.0.0.1:0").unwrap();
+        let guest = Connection::join(&host.local_address().unwrap().to_string());
+        wait_for(&host, |event| matches!(event, NetEvent::Connected(_)));
+        // the host answers a connection with the state, which must reach the guest
+        let mut board = Board::new();
+        board.set_current_player(HOST_SIDE);
+        assert!(host.send(&state_message(&board)));
+        let NetEvent::Received(NetMessage::State { board: state, to_move, .. }) =
+            wait_for(&guest, |event| matches!(event, NetEvent::Received(_)))
+        else {
+            panic!("expected a state");
+        };
+        assert_eq!((state.as_str(), to_move.as_str()), ("---------", "X"));
+        assert!(guest.is_connected());
+
+        assert!(guest.send(&NetMessage::Move { row: 1, col: 2 }));
+        let event = wait_for(&host, |event| matches!(event, NetEvent::Received(_)));
+        assert_eq!(event, NetEvent::Received(NetMessage::Move { row: 1, col: 2 }));
+        drop(guest);
+        wait_for(&host, |event| *event == NetEvent::Disconnected);
+        assert!(!host.is_connected());
+    }
+
+    #[test]
+    fn play_move_refuses_the_wrong_side_taken_cells_and_finished_games() {
+        let mut board = Board::new();
+        board.set_current_player(HOST_SIDE);
+        assert_eq!(play_move(&mut board, GUEST_SIDE, 0, 0), Err("it is not O's turn".to_string()));
+        assert_eq!(play_move(&mut board, HOST_SIDE, 0, 0), Ok(()));
+        assert_eq!(play_move(&mut board, GUEST_SIDE, 0, 0), Err("0,0 is not a legal move".to_string()));
+        assert_eq!(board.board_state(), "X--------");
+
+        let mut won = board_from_state(BoardSize::CLASSIC, "XXXOO----", "O").unwrap();
+        assert_eq!(play_move(&mut won, GUEST_SIDE, 2, 2), Err("the game is over".to_string()));
+        assert_eq!(won.board_state(), "XXXOO----");
+    }
+}
@@ -0,0 +1,365 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+use crate::dqn::DqnAgent;
+use crate::game::Agent;
+use crate::mcts::Mcts;
+use crate::{random, Board, BoardSize, Cell, QLearningAgent};
+use rand::Rng;
+use rand::prelude::IndexedRandom;
+use std::collections::{HashMap, HashSet};
+
+/// An opponent the learning agent can be paired with for a training episode.
+#[derive(Debug)]
+pub enum Opponent {
+    /// The agent plays both sides of the board (the original training loop).
+    SelfPlay,
+    /// Picks uniformly among the available moves.
+    Random,
+    /// Wins when it can, blocks when it must and plays randomly otherwise.
+    Blocking,
+    /// Perfect play, except that with `mistake_probability` it plays a random move instead.
+    Minimax {
+        mistake_probability: f64,
+        cache: HashMap<String, i32>,
+    },
+    /// A frozen agent, e.g. an earlier model loaded from disk. It always plays greedily.
+    Snapshot(Box<QLearningAgent>),
+    /// A frozen copy of the learner itself, refreshed every `interval` episodes.
+    /// Until the first copy is taken it behaves like `Random`.
+    PastSelf {
+        interval: usize,
+        agent: Option<Box<QLearningAgent>>,
+    },
+    /// Monte Carlo tree search, which needs no training and plays boards of any size.
+    Mcts(Box<Mcts<Board>>),
+    /// A network agent loaded from disk, playing greedily.
+    Network(Box<DqnAgent>),
+}
+
+impl Opponent {
+    pub fn parse(kind: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let (name, param) = match kind.split_once('@') {
+            Some((name, param)) => (name, Some(param)),
+            None => (kind, None),
+        };
+        let opponent = match (name, param) {
+            ("self", None) => Opponent::SelfPlay,
+            ("random", None) => Opponent::Random,
+            ("blocking", None) => Opponent::Blocking,
+            ("minimax", param) => {
+                let mistake_probability = param.map(str::parse).transpose()?.unwrap_or(0.0);
+                if !(0.0..=1.0).contains(&mistake_probability) {
+                    return Err(format!("minimax mistake probability must be within [0, 1], got {}", mistake_probability).into());
+                }
+                Opponent::Minimax { mistake_probability, cache: HashMap::new() }
+            }
+            ("snapshot", Some(path)) => {
+                let mut agent = QLearningAgent::load_from_file(path)?;
+                agent.metadata.check_standard_rules().map_err(|err| format!("{}: {}", path, err))?;
+                agent.train = false;
+                Opponent::Snapshot(Box::new(agent))
+            }
+            ("past", param) => {
+                let interval = param.map(str::parse).transpose()?.unwrap_or(10000);
+                if interval == 0 {
+                    return Err("past snapshot interval must be greater than zero".into());
+                }
+                Opponent::PastSelf { interval, agent: None }
+            }
+            ("mcts", options) => Opponent::Mcts(Box::new(Mcts::parse(options)?)),
+            ("network", Some(path)) => Opponent::Network(Box::new(DqnAgent::load_from_file(path)?)),
+            _ => return Err(format!("unknown opponent '{}'", kind).into()),
+        };
+        Ok(opponent)
+    }
+
+    /// Picks the opponent's move for the current player of `game`.
+    pub fn choose_move(&mut self, game: &Board) -> (usize, usize) {
+        let mut rng = random::rng();
+        let moves = game.available_moves();
+        match self {
+            Opponent::SelfPlay | Opponent::Random | Opponent::PastSelf { agent: None, .. } => {
+                *moves.choose(&mut rng).unwrap()
+            }
+            Opponent::Blocking => game
+                .find_winning_move()
+                .or_else(|| game.find_blocking_move())
+                .unwrap_or_else(|| *moves.choose(&mut rng).unwrap()),
+            Opponent::Minimax { mistake_probability, cache } => {
+                if rng.random::<f64>() < *mistake_probability {
+                    *moves.choose(&mut rng).unwrap()
+                } else {
+                    minimax_move(game, cache)
+                }
+            }
+            Opponent::Snapshot(agent) | Opponent::PastSelf { agent: Some(agent), .. } => {
+                let (action, _, _) = agent.choose_action(&game.board_state(), &moves, None);
+                action
+            }
+            Opponent::Mcts(mcts) => mcts.choose_move(game),
+            Opponent::Network(agent) => agent.greedy_action(game),
+        }
+    }
+
+    /// Fails if the opponent cannot play on boards of `size`: minimax searches the whole game
+    /// tree, which is only feasible on the 3×3 board, and snapshots only know their own board.
+    pub fn check_board(&self, size: BoardSize) -> Result<(), String> {
+        match self {
+            Opponent::Minimax { .. } if size != BoardSize::CLASSIC => {
+                Err(format!("the minimax opponent only plays 3x3 boards, not {}", size))
+            }
+            Opponent::Snapshot(agent) if agent.metadata.board != size => {
+                Err(format!("a snapshot trained on {} cannot play on {}", agent.metadata.board, size))
+            }
+            Opponent::Network(agent) if agent.metadata.board != size => {
+                Err(format!("a network trained on {} cannot play on {}", agent.metadata.board, size))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Refreshes frozen copies of the learner once their interval has elapsed.
+    fn refresh(&mut self, learner: &QLearningAgent, episode: usize) {
+        if let Opponent::PastSelf { interval, agent } = self
+            && episode.is_multiple_of(*interval)
+        {
+            let mut snapshot = learner.clone();
+            snapshot.train = false;
+            *agent = Some(Box::new(snapshot));
+        }
+    }
+}
+
+/// A weighted set of opponents; one of them is sampled for every training episode.
+#[derive(Debug)]
+pub struct OpponentPool {
+    entries: Vec<(Opponent, f64)>,
+}
+
+impl OpponentPool {
+    pub fn self_play() -> Self {
+        OpponentPool { entries: vec![(Opponent::SelfPlay, 1.0)] }
+    }
+
+    /// Parses a comma separated list of `kind[@param][:weight]` entries, e.g.
+    /// `self:0.5,random:0.1,blocking:0.1,minimax@0.2:0.2,past@20000:0.1`.
+    /// `snapshot@<file>` loads a frozen agent from a saved model file, `network@<file>` a network
+    /// agent, and `mcts@<options>` searches with the options of `Mcts::parse`.
+    pub fn parse(spec: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut entries = Vec::new();
+        for entry in spec.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (kind, weight) = match entry.rsplit_once(':') {
+                Some((kind, weight)) => match weight.parse::<f64>() {
+                    Ok(weight) => (kind, weight),
+                    Err(_) => (entry, 1.0),
+                },
+                None => (entry, 1.0),
+            };
+            if !(weight.is_finite() && weight > 0.0) {
+                return Err(format!("opponent weight must be positive, got {} for '{}'", weight, kind).into());
+            }
+            entries.push((Opponent::parse(kind)?, weight));
+        }
+        if entries.is_empty() {
+            return Err("opponent specification is empty".into());
+        }
+        Ok(OpponentPool { entries })
+    }
+
+    /// Samples an opponent for the next episode, proportionally to its weight.
+    pub fn sample(&mut self) -> &mut Opponent {
+        let total: f64 = self.entries.iter().map(|(_, weight)| weight).sum();
+        let mut target = random::rng().random::<f64>() * total;
+        let mut index = self.entries.len() - 1;
+        for (i, (_, weight)) in self.entries.iter().enumerate() {
+            if target < *weight {
+                index = i;
+                break;
+            }
+            target -= weight;
+        }
+        &mut self.entries[index].0
+    }
+
+    pub fn check_board(&self, size: BoardSize) -> Result<(), String> {
+        self.entries.iter().try_for_each(|(opponent, _)| opponent.check_board(size))
+    }
+
+    /// Whether every episode is played against the learner itself.
+    pub fn is_self_play(&self) -> bool {
+        self.entries.iter().all(|(opponent, _)| matches!(opponent, Opponent::SelfPlay))
+    }
+
+    /// Whether any entry plays frozen copies of the learner, which only tabular learners have.
+    pub fn has_past_self(&self) -> bool {
+        self.entries.iter().any(|(opponent, _)| matches!(opponent, Opponent::PastSelf { .. }))
+    }
+
+    pub fn refresh(&mut self, learner: &QLearningAgent, episode: usize) {
+        for (opponent, _) in self.entries.iter_mut() {
+            opponent.refresh(learner, episode);
+        }
+    }
+}
+
+fn minimax_move(game: &Board, cache: &mut HashMap<String, i32>) -> (usize, usize) {
+    let scores = move_scores(game, cache);
+    let best_score = scores.iter().map(|&(_, score)| score).max().unwrap();
+    let best_moves: Vec<(usize, usize)> = scores
+        .into_iter()
+        .filter(|&(_, score)| score == best_score)
+        .map(|(pos, _)| pos)
+        .collect();
+    *best_moves.choose(&mut random::rng()).unwrap()
+}
+
+/// The minimax score of every available move, from the point of view of the player to move.
+pub fn move_scores(game: &Board, cache: &mut HashMap<String, i32>) -> Vec<((usize, usize), i32)> {
+    game.available_moves()
+        .into_iter()
+        .map(|pos| {
+            let mut child = game.clone();
+            child.make_move(pos.0, pos.1);
+            (pos, -negamax(&child, cache))
+        })
+        .collect()
+}
+
+/// Scores `game` from the point of view of the player to move. Faster wins score higher.
+fn negamax(game: &Board, cache: &mut HashMap<String, i32>) -> i32 {
+    let key = format!("{}{}", game.board_state(), game.get_current_player().marker);
+    if let Some(&score) = cache.get(&key) {
+        return score;
+    }
+    let moves = game.available_moves();
+    let score = if game.check_winner().is_some() {
+        // the previous player completed a line
+        -(moves.len() as i32 + 1)
+    } else if moves.is_empty() {
+        0
+    } else {
+        moves
+            .iter()
+            .map(|pos| {
+                let mut child = game.clone();
+                child.make_move(pos.0, pos.1);
+                -negamax(&child, cache)
+            })
+            .max()
+            .unwrap()
+    };
+    cache.insert(key, score);
+    score
+}
+
+/// The result of `audit`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Audit {
+    pub positions: usize,
+    /// Moves that turned a won position into a draw or loss, or a drawn one into a loss.
+    pub mistakes: usize,
+}
+
+/// Checks an agent for perfect play on the 3×3 board: it plays either side, with either side
+/// starting, against every reply, and its move in every position it reaches is compared with
+/// the minimax outcome of the best one.
+pub fn audit(agent: &mut dyn Agent<Board>) -> Audit {
+    let mut audit = Audit::default();
+    let mut cache = HashMap::new();
+    let mut seen = HashSet::new();
+    for agent_marker in [Cell::X, Cell::O] {
+        for first_player in [Cell::X, Cell::O] {
+            let mut game = Board::new();
+            game.set_current_player(first_player);
+            audit_from(&game, agent_marker, agent, &mut cache, &mut seen, &mut audit);
+        }
+    }
+    audit
+}
+
+fn audit_from(
+    game: &Board,
+    agent_marker: Cell,
+    agent: &mut dyn Agent<Board>,
+    cache: &mut HashMap<String, i32>,
+    seen: &mut HashSet<String>,
+    audit: &mut Audit,
+) {
+    if game.is_game_over().0 || !seen.insert(format!("{}{}{}", game.board_state(), game.get_current_player().marker, agent_marker)) {
+        return;
+    }
+    let replies = if game.get_current_player().marker == agent_marker {
+        let scores = move_scores(game, cache);
+        let best = scores.iter().map(|&(_, score)| score.signum()).max().unwrap();
+        let action = agent.choose_move(game);
+        audit.positions += 1;
+        if scores.iter().any(|&(pos, score)| pos == action && score.signum() < best) {
+            audit.mistakes += 1;
+        }
+        vec![action]
+    } else {
+        game.available_moves()
+    };
+    for (row, col) in replies {
+        let mut child = game.clone();
+        child.make_move(row, col);
+        audit_from(&child, agent_marker, agent, cache, seen, audit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Player;
+
+    #[test]
+    fn empty_board_is_a_draw() {
+        let mut cache = HashMap::new();
+        assert_eq!(negamax(&Board::new(), &mut cache), 0);
+        let scores = move_scores(&Board::new(), &mut cache);
+        assert_eq!(scores.len(), 9);
+        assert!(scores.iter().all(|&(_, score)| score == 0));
+    }
+
+    #[test]
+    fn minimax_takes_a_win_and_blocks_a_loss() {
+        let mut minimax = Opponent::parse("minimax").unwrap();
+        // O to move wins on the middle row rather than blocking the top one
+        let game = Board::from_state("XX-OO-X--").unwrap();
+        assert_eq!(minimax.choose_move(&game), (1, 2));
+        // O to move cannot win, so it blocks the diagonal
+        let game = Board::from_state("X---X-O--").unwrap();
+        assert_eq!(minimax.choose_move(&game), (2, 2));
+    }
+
+    #[test]
+    fn minimax_never_loses_to_random() {
+        random::seed(26);
+        let mut minimax = Opponent::parse("minimax").unwrap();
+        let mut random_player = Opponent::Random;
+        for game_number in 0..200 {
+            let minimax_marker = if game_number % 2 == 0 { Cell::X } else { Cell::O };
+            let mut game = Board::new();
+            while !game.is_game_over().0 {
+                let player = if game.get_current_player().marker == minimax_marker { &mut minimax } else { &mut random_player };
+                let (row, col) = player.choose_move(&game);
+                game.make_move(row, col);
+            }
+            assert_ne!(game.check_winner(), Some(Player::new(minimax_marker).opponent().marker), "minimax lost: {}", game);
+        }
+    }
+
+    #[test]
+    fn audit_passes_minimax_and_fails_random() {
+        random::seed(50);
+        let minimax = audit(&mut Opponent::parse("minimax").unwrap());
+        assert_eq!(minimax.mistakes, 0);
+        assert!(minimax.positions > 0);
+        let random_player = audit(&mut Opponent::Random);
+        assert!(random_player.mistakes > 0 && random_player.mistakes < random_player.positions);
+    }
+}
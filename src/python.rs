@@ -0,0 +1,286 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! Python bindings, built with the `python` feature.
+//!
+//! Build the module with `maturin develop` (see `pyproject.toml`), which builds the library as
+//! a cdylib itself, or with
+//! `cargo rustc --release --lib --features python --crate-type cdylib` and copy the library
+//! to `q_learning_tictactoe.so` (`.pyd` on Windows) next to the notebook. Training releases
+//! the GIL, so other Python threads keep running meanwhile.
+
+use crate::convergence;
+use crate::env::{Observation, Rewards, TicTacToeEnv};
+use crate::model;
+use crate::opponent::{Opponent, OpponentPool};
+use crate::{train_q_learning, Board, BoardSize, Cell, QLearningAgent, TrainingOptions};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::HashMap;
+use std::fmt::Display;
+
+fn error(err: impl Display) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+fn side(name: &str) -> PyResult<Cell> {
+    match name {
+        "X" | "x" => Ok(Cell::X),
+        "O" | "o" => Ok(Cell::O),
+        _ => Err(error(format!("unknown side '{}'", name))),
+    }
+}
+
+/// A position. The side to move follows from the marker counts, or from `to_move` (X by
+/// default) when both sides have played equally often.
+#[pyclass(name = "Board")]
+#[derive(Clone)]
+struct PyBoard {
+    board: Board,
+}
+
+#[pymethods]
+impl PyBoard {
+    #[new]
+    #[pyo3(signature = (state = "---------", to_move = None))]
+    fn new(state: &str, to_move: Option<&str>) -> PyResult<Self> {
+        let mut board = Board::from_state(state).ok_or_else(|| error(format!("invalid board state '{}'", state)))?;
+        if state.matches('X').count() == state.matches('O').count() {
+            board.set_current_player(to_move.map(side).transpose()?.unwrap_or(Cell::X));
+        }
+        Ok(PyBoard { board })
+    }
+
+    #[getter]
+    fn state(&self) -> String {
+        self.board.board_state()
+    }
+
+    #[getter]
+    fn to_move(&self) -> String {
+        self.board.get_current_player().marker.to_string()
+    }
+
+    /// The empty cells as `(row, col)`, or none once the game is over.
+    fn legal_moves(&self) -> Vec<(usize, usize)> {
+        if self.board.is_game_over().0 { Vec::new() } else { self.board.available_moves() }
+    }
+
+    /// Plays for the side to move and returns whether the game is over.
+    fn make_move(&mut self, row: usize, col: usize) -> PyResult<bool> {
+        if !self.legal_moves().contains(&(row, col)) {
+            return Err(error(format!("{},{} is not a legal move", row, col)));
+        }
+        Ok(self.board.make_move(row, col).0.game_over)
+    }
+
+    fn winner(&self) -> Option<String> {
+        self.board.check_winner().map(|winner| winner.to_string())
+    }
+
+    fn is_over(&self) -> bool {
+        self.board.is_game_over().0
+    }
+
+    fn __str__(&self) -> String {
+        let state: Vec<char> = self.board.board_state().chars().collect();
+        state.chunks(self.board.size().width).map(|row| row.iter().collect::<String>()).collect::<Vec<_>>().join("\n")
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Board('{}', to_move='{}')", self.state(), self.to_move())
+    }
+}
+
+/// The Gym-style environment: `reset` and `step` return observations as dictionaries with
+/// `cells` (1 for the learner, -1 for the opponent), `mask` and `board`. `board_size` takes
+/// `WIDTHxHEIGHT[:K]`, e.g. `4x4:3`.
+#[pyclass(name = "Env")]
+struct PyEnv {
+    env: TicTacToeEnv,
+}
+
+fn observation_dict<'py>(py: Python<'py>, observation: &Observation) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("cells", &observation.cells)?;
+    dict.set_item("mask", &observation.mask)?;
+    dict.set_item("board", &observation.board_state)?;
+    Ok(dict)
+}
+
+#[pymethods]
+impl PyEnv {
+    #[new]
+    #[pyo3(signature = (opponent = "random", learner = None, win = 1.0, draw = 0.0, loss = -1.0, illegal = -1.0, step = 0.0, board_size = "3x3"))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(opponent: &str, learner: Option<&str>, win: f64, draw: f64, loss: f64, illegal: f64, step: f64, board_size: &str) -> PyResult<Self> {
+        let size = BoardSize::parse(board_size).map_err(error)?;
+        let mut env = TicTacToeEnv::with_size(Opponent::parse(opponent).map_err(error)?, size).map_err(error)?;
+        env.learner_side = learner.map(side).transpose()?;
+        env.rewards = Rewards { win, draw, loss, illegal, step };
+        Ok(PyEnv { env })
+    }
+
+    #[pyo3(signature = (seed = None))]
+    fn reset<'py>(&mut self, py: Python<'py>, seed: Option<u64>) -> PyResult<Bound<'py, PyDict>> {
+        observation_dict(py, &self.env.reset(seed))
+    }
+
+    /// Returns `(observation, reward, done, info)` for the action `row * width + col`.
+    #[allow(clippy::type_complexity)]
+    fn step<'py>(&mut self, py: Python<'py>, action: usize) -> PyResult<(Bound<'py, PyDict>, f64, bool, Bound<'py, PyDict>)> {
+        let (observation, reward, done, info) = self.env.step(action).map_err(error)?;
+        let info_dict = PyDict::new(py);
+        info_dict.set_item("learner", info.learner.to_string())?;
+        info_dict.set_item("winner", info.winner.map(|winner| winner.to_string()))?;
+        info_dict.set_item("illegal_action", info.illegal_action)?;
+        info_dict.set_item("opponent_action", info.opponent_action)?;
+        Ok((observation_dict(py, &observation)?, reward, done, info_dict))
+    }
+
+    fn legal_action_mask(&self) -> Vec<bool> {
+        self.env.legal_action_mask()
+    }
+
+    #[getter]
+    fn learner(&self) -> String {
+        self.env.learner().to_string()
+    }
+
+    #[getter]
+    fn board(&self) -> PyBoard {
+        PyBoard { board: self.env.board().clone() }
+    }
+}
+
+/// A tabular Q-learning agent. Outside `train` it always plays its greedy move.
+#[pyclass(name = "Agent")]
+struct PyAgent {
+    agent: QLearningAgent,
+}
+
+#[pymethods]
+impl PyAgent {
+    #[new]
+    #[pyo3(signature = (alpha = 0.08, gamma = 0.7, epsilon = 0.9))]
+    fn new(alpha: f64, gamma: f64, epsilon: f64) -> Self {
+        let mut agent = QLearningAgent::new(alpha, gamma, epsilon);
+        agent.train = false;
+        PyAgent { agent }
+    }
+
+    #[staticmethod]
+    fn load(path: &str) -> PyResult<Self> {
+        let agent = QLearningAgent::load_from_file(path).map_err(error)?;
+        agent.metadata.check_standard_rules().map_err(error)?;
+        Ok(PyAgent { agent })
+    }
+
+    /// Saves as JSON, or in the binary format if `path` ends in `.bin`.
+    fn save(&self, path: &str) -> PyResult<()> {
+        self.agent.save_to_path(path).map_err(error)
+    }
+
+    /// Trains against `opponents`, a pool as accepted by `train --opponents`. The model is
+    /// saved to `output` if given.
+    #[pyo3(signature = (episodes, opponents = "self", seed = None, output = None))]
+    fn train(&mut self, py: Python<'_>, episodes: usize, opponents: &str, seed: Option<u64>, output: Option<String>) -> PyResult<()> {
+        let opponents = OpponentPool::parse(opponents).map_err(error)?;
+        let agent = &mut self.agent;
+        py.allow_threads(move || {
+            let mut options = TrainingOptions::new(episodes);
+            options.opponents = opponents;
+            options.seed = seed;
+            options.output = output;
+            train_q_learning(agent, &mut options);
+            agent.train = false;
+        });
+        Ok(())
+    }
+
+    /// Plays `games` greedy games against `opponent`, alternating sides, and returns the
+    /// wins, draws, losses and score.
+    #[pyo3(signature = (opponent = "minimax", games = 100))]
+    fn evaluate<'py>(&self, py: Python<'py>, opponent: &str, games: usize) -> PyResult<Bound<'py, PyDict>> {
+        let mut opponent = Opponent::parse(opponent).map_err(error)?;
+        let evaluation = convergence::evaluate(&self.agent, &mut opponent, games);
+        let dict = PyDict::new(py);
+        dict.set_item("wins", evaluation.wins)?;
+        dict.set_item("draws", evaluation.draws)?;
+        dict.set_item("losses", evaluation.losses)?;
+        dict.set_item("score", evaluation.score())?;
+        Ok(dict)
+    }
+
+    /// The move the agent plays on `board`, as `(row, col)`.
+    fn choose_move(&mut self, board: &PyBoard) -> PyResult<(usize, usize)> {
+        let moves = board.legal_moves();
+        if moves.is_empty() {
+            return Err(error("the game is over"));
+        }
+        let (action, _, _) = self.agent.choose_action(&board.state(), &moves, None);
+        Ok(action)
+    }
+
+    /// The learned values of the moves in a `board_state`, keyed by `"row,col"`.
+    fn q_values(&self, state: &str) -> HashMap<String, f64> {
+        self.agent.q_table.get(state).map_or_else(HashMap::new, |actions| {
+            actions.iter().map(|(action, entry)| (action.clone(), entry.value)).collect()
+        })
+    }
+
+    #[getter]
+    fn alpha(&self) -> f64 {
+        self.agent.alpha
+    }
+
+    #[setter]
+    fn set_alpha(&mut self, alpha: f64) {
+        self.agent.alpha = alpha;
+    }
+
+    #[getter]
+    fn gamma(&self) -> f64 {
+        self.agent.gamma
+    }
+
+    #[setter]
+    fn set_gamma(&mut self, gamma: f64) {
+        self.agent.gamma = gamma;
+    }
+
+    #[getter]
+    fn epsilon(&self) -> f64 {
+        self.agent.epsilon
+    }
+
+    #[setter]
+    fn set_epsilon(&mut self, epsilon: f64) {
+        self.agent.epsilon = epsilon;
+    }
+
+    /// The number of states in the Q-table.
+    fn __len__(&self) -> usize {
+        self.agent.q_table.len()
+    }
+}
+
+/// The header of a saved model as JSON, read without loading the Q-table.
+#[pyfunction]
+fn read_header(path: &str) -> PyResult<String> {
+    serde_json::to_string(&model::read_header(path).map_err(error)?).map_err(error)
+}
+
+#[pymodule]
+#[pyo3(name = "q_learning_tictactoe")]
+fn python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyBoard>()?;
+    m.add_class::<PyEnv>()?;
+    m.add_class::<PyAgent>()?;
+    m.add_function(wrap_pyfunction!(read_header, m)?)?;
+    Ok(())
+}
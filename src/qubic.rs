@@ -0,0 +1,227 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! Qubic: four in a row on a 4×4×4 cube.
+//!
+//! The cube is four stacked 4×4 layers, each an ordinary `Board` that holds its cells and
+//! checks moves. Besides the rows, columns and diagonals within a layer, lines run straight
+//! down through the layers and diagonally across them, 76 in all. The first player to fill
+//! one wins.
+//!
+//! Moves are `(layer, row, col)`.
+
+use crate::{random, Board, BoardSize, Cell, MoveStatus, Player};
+use rand::Rng;
+use rand::prelude::IndexedRandom;
+use std::fmt;
+use std::sync::LazyLock;
+
+/// The number of layers, and of rows and columns in each.
+pub const SIDE: usize = 4;
+const LAYER: BoardSize = BoardSize { width: SIDE, height: SIDE, k: SIDE };
+
+/// A cell of the cube as `(layer, row, col)`.
+pub type Position = (usize, usize, usize);
+
+/// The 76 winning lines: 40 within the layers, 16 straight through them and 20 diagonally across.
+pub static LINES: LazyLock<Vec<[Position; SIDE]>> = LazyLock::new(|| {
+    let range = 0..SIDE as isize;
+    let mut lines = Vec::new();
+    for step in [-1isize, 0, 1].iter().flat_map(|&l| [-1isize, 0, 1].iter().flat_map(move |&r| [-1isize, 0, 1].map(|c| (l, r, c)))) {
+        // one of the two opposite steps along each of the 13 axes
+        if step <= (0, 0, 0) {
+            continue;
+        }
+        for start in 0..SIDE * SIDE * SIDE {
+            let start = ((start / (SIDE * SIDE)) as isize, (start / SIDE % SIDE) as isize, (start % SIDE) as isize);
+            let last = SIDE as isize - 1;
+            let end = (start.0 + step.0 * last, start.1 + step.1 * last, start.2 + step.2 * last);
+            if range.contains(&end.0) && range.contains(&end.1) && range.contains(&end.2) {
+                lines.push(std::array::from_fn(|i| {
+                    let i = i as isize;
+                    ((start.0 + step.0 * i) as usize, (start.1 + step.1 * i) as usize, (start.2 + step.2 * i) as usize)
+                }));
+            }
+        }
+    }
+    debug_assert_eq!(lines.len(), 76);
+    lines
+});
+
+/// The indices into `LINES` of the lines through each cell, by `index`.
+static LINES_THROUGH: LazyLock<Vec<Vec<usize>>> = LazyLock::new(|| {
+    let mut through = vec![Vec::new(); SIDE * SIDE * SIDE];
+    for (i, line) in LINES.iter().enumerate() {
+        for &pos in line {
+            through[index(pos)].push(i);
+        }
+    }
+    through
+});
+
+fn index((layer, row, col): Position) -> usize {
+    (layer * SIDE + row) * SIDE + col
+}
+
+#[derive(Debug, Clone)]
+pub struct QubicBoard {
+    /// The layers from the top.
+    layers: [Board; SIDE],
+    pub players: [Player; 2],
+    pub current_player: usize,
+    winner: Option<Cell>,
+    empty: usize,
+}
+
+impl QubicBoard {
+    pub fn new() -> Self {
+        QubicBoard {
+            layers: std::array::from_fn(|_| Board::with_size(LAYER)),
+            players: [Player::new(Cell::X), Player::new(Cell::O)],
+            current_player: random::rng().random_range(0..=1),
+            winner: None,
+            empty: SIDE * SIDE * SIDE,
+        }
+    }
+
+    pub fn get_current_player(&self) -> &Player {
+        &self.players[self.current_player]
+    }
+
+    pub fn cell(&self, layer: usize, row: usize, col: usize) -> Cell {
+        self.layers[layer].cell(row, col)
+    }
+
+    pub fn make_move(&mut self, layer: usize, row: usize, col: usize) -> (MoveStatus, Option<Cell>) {
+        if layer >= SIDE || self.winner.is_some() {
+            return (MoveStatus::new(false, false), None);
+        }
+        let board = &mut self.layers[layer];
+        board.current_player = self.current_player;
+        let (status, _) = board.make_move(row, col);
+        if !status.move_successful {
+            return (status, None);
+        }
+        self.empty -= 1;
+        let marker = self.get_current_player().marker;
+        if self.completes_line((layer, row, col), marker) {
+            self.winner = Some(marker);
+        }
+        self.switch_turn();
+        let (game_over, winner) = self.is_game_over();
+        (MoveStatus::new(true, game_over), winner)
+    }
+
+    pub fn switch_turn(&mut self) {
+        self.current_player = 1 - self.current_player;
+    }
+
+    /// Whether `marker` at `pos` would complete a line, whatever the cell holds now.
+    pub fn completes_line(&self, pos: Position, marker: Cell) -> bool {
+        LINES_THROUGH[index(pos)].iter().any(|&line| {
+            LINES[line].iter().all(|&cell| cell == pos || self.cell(cell.0, cell.1, cell.2) == marker)
+        })
+    }
+
+    pub fn check_winner(&self) -> Option<Cell> {
+        self.winner
+    }
+
+    pub fn is_draw(&self) -> bool {
+        self.empty == 0
+    }
+
+    pub fn is_game_over(&self) -> (bool, Option<Cell>) {
+        let winner = self.check_winner();
+        (winner.is_some() || self.is_draw(), winner)
+    }
+
+    /// The layers' states from the top, 64 cells in all.
+    pub fn board_state(&self) -> String {
+        self.layers.iter().map(Board::board_state).collect()
+    }
+
+    pub fn available_moves(&self) -> Vec<Position> {
+        (0..SIDE)
+            .flat_map(|layer| self.layers[layer].available_moves().into_iter().map(move |(row, col)| (layer, row, col)))
+            .collect()
+    }
+
+    pub fn reset(&mut self) {
+        *self = QubicBoard::new();
+    }
+}
+
+impl Default for QubicBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for QubicBoard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.board_state())
+    }
+}
+
+/// A computer player for Qubic.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QubicOpponent {
+    /// Picks uniformly among the legal moves.
+    Random,
+    /// Scores each empty cell by the lines through it, see `score_move`.
+    Heuristic,
+}
+
+impl QubicOpponent {
+    /// Parses `random` or `heuristic`.
+    pub fn parse(kind: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        match kind {
+            "random" => Ok(QubicOpponent::Random),
+            "heuristic" => Ok(QubicOpponent::Heuristic),
+            _ => Err(format!("unknown qubic opponent '{}'", kind).into()),
+        }
+    }
+
+    /// Picks a move for the current player; the game must not be over. Equally good moves are
+    /// chosen between at random.
+    pub fn choose_move(&self, game: &QubicBoard) -> Position {
+        let mut rng = random::rng();
+        let moves = game.available_moves();
+        match self {
+            QubicOpponent::Random => *moves.choose(&mut rng).unwrap(),
+            QubicOpponent::Heuristic => {
+                let marker = game.get_current_player().marker;
+                let scores: Vec<i32> = moves.iter().map(|&pos| score_move(game, pos, marker)).collect();
+                let best_score = *scores.iter().max().unwrap();
+                let best_moves: Vec<Position> =
+                    moves.into_iter().zip(scores).filter(|&(_, score)| score == best_score).map(|(pos, _)| pos).collect();
+                *best_moves.choose(&mut rng).unwrap()
+            }
+        }
+    }
+}
+
+/// How much `marker` at `pos` is worth: completing a line beats blocking the opponent's three,
+/// which beats building on lines the opponent has not played in or getting in the way of theirs.
+pub fn score_move(game: &QubicBoard, pos: Position, marker: Cell) -> i32 {
+    const BUILD: [i32; SIDE] = [1, 4, 32, 100_000];
+    const BLOCK: [i32; SIDE] = [0, 3, 24, 10_000];
+    LINES_THROUGH[index(pos)]
+        .iter()
+        .map(|&line| {
+            let line = LINES[line];
+            let owners = line.map(|(layer, row, col)| game.cell(layer, row, col));
+            let mine = owners.iter().filter(|&&owner| owner == marker).count();
+            let theirs = owners.iter().filter(|&&owner| owner != marker && owner != Cell::Empty).count();
+            match (mine, theirs) {
+                (mine, 0) => BUILD[mine],
+                (0, theirs) => BLOCK[theirs],
+                _ => 0,
+            }
+        })
+        .sum()
+}
@@ -0,0 +1,257 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+use crate::{random, QLearningAgent};
+use rand::Rng;
+
+/// Keeps prioritised sampling from ever starving a transition completely.
+const MIN_PRIORITY: f64 = 1e-3;
+
+#[derive(Debug, Clone)]
+pub struct Transition {
+    pub state: String,
+    pub action: String,
+    pub reward: f64,
+    pub next_state: String,
+    pub done: bool,
+}
+
+impl Transition {
+    /// Applies the transition as a Q-learning update and returns its TD error.
+    pub fn apply(&self, agent: &mut QLearningAgent) -> f64 {
+        if self.done {
+            agent.update_towards(&self.state, &self.action, self.reward)
+        } else {
+            agent.update_q_value(&self.state, &self.action, self.reward, &self.next_state)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sampling {
+    Uniform,
+    /// Samples proportionally to `|TD error| ^ exponent`.
+    Prioritized { exponent: f64 },
+}
+
+impl Sampling {
+    /// Parses `uniform`, `prioritized` or `prioritized@<exponent>`.
+    pub fn parse(spec: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        match spec.split_once('@') {
+            None if spec == "uniform" => Ok(Sampling::Uniform),
+            None if spec == "prioritized" => Ok(Sampling::Prioritized { exponent: 0.6 }),
+            Some(("prioritized", exponent)) => Ok(Sampling::Prioritized { exponent: exponent.parse()? }),
+            _ => Err(format!("unknown replay sampling '{}'", spec).into()),
+        }
+    }
+}
+
+/// A bounded buffer of past transitions that are re-applied to the Q-table.
+///
+/// After every new transition `replay_ratio` stored transitions are replayed on average;
+/// fractional ratios carry over between transitions. Once full, the oldest entries are
+/// overwritten.
+#[derive(Debug)]
+pub struct ReplayBuffer {
+    transitions: Vec<Transition>,
+    capacity: usize,
+    next: usize,
+    pub sampling: Sampling,
+    pub replay_ratio: f64,
+    credit: f64,
+    priorities: SumTree,
+}
+
+impl ReplayBuffer {
+    pub fn new(capacity: usize, sampling: Sampling, replay_ratio: f64) -> Self {
+        ReplayBuffer {
+            transitions: Vec::with_capacity(capacity),
+            capacity,
+            next: 0,
+            sampling,
+            replay_ratio,
+            credit: 0.0,
+            priorities: SumTree::new(capacity),
+        }
+    }
+
+    pub fn push(&mut self, transition: Transition, td_error: f64) {
+        if self.transitions.len() < self.capacity {
+            self.transitions.push(transition);
+        } else {
+            self.transitions[self.next] = transition;
+        }
+        self.priorities.update(self.next, self.priority(td_error));
+        self.next = (self.next + 1) % self.capacity;
+    }
+
+    /// Replays the number of stored transitions owed by the replay ratio.
+    pub fn replay(&mut self, agent: &mut QLearningAgent) {
+        if self.transitions.is_empty() {
+            return;
+        }
+        self.credit += self.replay_ratio;
+        while self.credit >= 1.0 {
+            self.credit -= 1.0;
+            let index = self.sample();
+            let td_error = self.transitions[index].apply(agent);
+            self.priorities.update(index, self.priority(td_error));
+        }
+    }
+
+    fn sample(&self) -> usize {
+        let mut rng = random::rng();
+        match self.sampling {
+            Sampling::Uniform => rng.random_range(0..self.transitions.len()),
+            Sampling::Prioritized { .. } => {
+                let target = rng.random::<f64>() * self.priorities.total();
+                self.priorities.find(target).min(self.transitions.len() - 1)
+            }
+        }
+    }
+
+    fn priority(&self, td_error: f64) -> f64 {
+        match self.sampling {
+            Sampling::Uniform => 1.0,
+            Sampling::Prioritized { exponent } => (td_error.abs() + MIN_PRIORITY).powf(exponent),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.transitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transitions.is_empty()
+    }
+}
+
+/// A binary tree whose inner nodes hold the sum of their children, for O(log n)
+/// proportional sampling over the leaves.
+#[derive(Debug)]
+struct SumTree {
+    nodes: Vec<f64>,
+    leaves: usize,
+}
+
+impl SumTree {
+    fn new(capacity: usize) -> Self {
+        let leaves = capacity.next_power_of_two();
+        SumTree { nodes: vec![0.0; 2 * leaves], leaves }
+    }
+
+    fn update(&mut self, index: usize, value: f64) {
+        let mut node = index + self.leaves;
+        self.nodes[node] = value;
+        while node > 1 {
+            node /= 2;
+            self.nodes[node] = self.nodes[2 * node] + self.nodes[2 * node + 1];
+        }
+    }
+
+    fn total(&self) -> f64 {
+        self.nodes[1]
+    }
+
+    /// The leaf in which the running sum crosses `target`.
+    fn find(&self, mut target: f64) -> usize {
+        let mut node = 1;
+        while node < self.leaves {
+            let left = 2 * node;
+            if target < self.nodes[left] {
+                node = left;
+            } else {
+                target -= self.nodes[left];
+                node = left + 1;
+            }
+        }
+        node - self.leaves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transition(number: usize) -> Transition {
+        Transition { state: number.to_string(), action: "0,0".to_string(), reward: 0.0, next_state: String::new(), done: true }
+    }
+
+    /// Whether every inner node holds the sum of its children.
+    fn sums_hold(tree: &SumTree) -> bool {
+        (1..tree.leaves).all(|node| (tree.nodes[node] - tree.nodes[2 * node] - tree.nodes[2 * node + 1]).abs() < 1e-9)
+    }
+
+    #[test]
+    fn sum_tree_keeps_its_sums() {
+        random::seed(28);
+        let mut tree = SumTree::new(13);
+        assert_eq!(tree.leaves, 16);
+        let mut values = [0.0; 13];
+        for _ in 0..200 {
+            let index = random::rng().random_range(0..13);
+            values[index] = random::rng().random::<f64>();
+            tree.update(index, values[index]);
+            assert!(sums_hold(&tree));
+            assert!((tree.total() - values.iter().sum::<f64>()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn sum_tree_finds_the_leaf_of_a_running_sum() {
+        let mut tree = SumTree::new(4);
+        for (index, value) in [1.0, 0.0, 2.0, 3.0].into_iter().enumerate() {
+            tree.update(index, value);
+        }
+        assert_eq!(tree.total(), 6.0);
+        assert_eq!(tree.find(0.0), 0);
+        assert_eq!(tree.find(0.999), 0);
+        // the empty leaf is never found
+        assert_eq!(tree.find(1.0), 2);
+        assert_eq!(tree.find(2.999), 2);
+        assert_eq!(tree.find(3.0), 3);
+        assert_eq!(tree.find(5.999), 3);
+    }
+
+    #[test]
+    fn prioritized_sampling_is_proportional_to_priority() {
+        random::seed(28);
+        let mut buffer = ReplayBuffer::new(5, Sampling::Prioritized { exponent: 1.0 }, 1.0);
+        let errors = [0.0, 1.0, 2.0, 3.0, 4.0];
+        for (number, &td_error) in errors.iter().enumerate() {
+            buffer.push(transition(number), td_error);
+        }
+        let mut counts = [0usize; 5];
+        let samples = 100_000;
+        for _ in 0..samples {
+            counts[buffer.sample()] += 1;
+        }
+        let total: f64 = errors.iter().map(|error| error + MIN_PRIORITY).sum();
+        for (count, error) in counts.iter().zip(errors) {
+            let expected = (error + MIN_PRIORITY) / total;
+            let observed = *count as f64 / samples as f64;
+            assert!((observed - expected).abs() < 0.01, "sampled {} for an expected {}", observed, expected);
+        }
+    }
+
+    #[test]
+    fn full_buffer_overwrites_the_oldest_transitions() {
+        random::seed(28);
+        let mut buffer = ReplayBuffer::new(3, Sampling::Prioritized { exponent: 1.0 }, 1.0);
+        for number in 0..5 {
+            buffer.push(transition(number), 1.0);
+        }
+        assert_eq!(buffer.len(), 3);
+        let states: Vec<&str> = buffer.transitions.iter().map(|transition| transition.state.as_str()).collect();
+        assert_eq!(states, ["3", "4", "2"]);
+        assert_eq!(buffer.next, 2);
+        // overwriting a slot replaces its priority rather than adding to it
+        buffer.push(transition(5), 9.0);
+        assert!((buffer.priorities.total() - (2.0 * (1.0 + MIN_PRIORITY) + 9.0 + MIN_PRIORITY)).abs() < 1e-9);
+        assert!(sums_hold(&buffer.priorities));
+        assert!((0..1000).all(|_| buffer.sample() < 3));
+    }
+}
@@ -0,0 +1,473 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! Rule variants played on the same boards: misère, Wild, Notakto and numerical tic-tac-toe.
+//!
+//! A `Board` only knows which marker completed a line; the `Rules` decide what that means, and
+//! which pieces the players may place. The players keep their X and O markers as names, even
+//! in variants where they place something else.
+//!
+//! The Q-learner plays these variants through `RulesGame`, whose states and moves make their
+//! own Q-table keys. A move is keyed `board,row,col,piece`.
+
+use crate::metrics::EpisodeStats;
+use crate::{random, Board, BoardSize, Cell, MoveStatus, Player, QLearningAgent, LINES};
+use rand::Rng;
+use rand::prelude::IndexedRandom;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Rules {
+    /// Completing a line of your own marker wins.
+    #[default]
+    Standard,
+    /// Completing a line of your own marker loses.
+    Misere,
+    /// Either player may place an X or an O, and whoever completes a line of either wins.
+    Wild,
+    /// Both players place X on `boards` boards. A board with a line is dead, and whoever
+    /// kills the last one loses.
+    Notakto { boards: usize },
+    /// On the 3×3 board the first player places the odd numbers from 1 to 9 and the second
+    /// the even ones, each once. Whoever completes a line of three numbers summing to 15 wins.
+    Numerical,
+}
+
+impl Rules {
+    pub const NOTAKTO_BOARDS: usize = 2;
+    pub const MAX_NOTAKTO_BOARDS: usize = 5;
+    /// Every variant, with Notakto on its default number of boards.
+    pub const ALL: [Rules; 5] = [
+        Rules::Standard,
+        Rules::Misere,
+        Rules::Wild,
+        Rules::Notakto { boards: Self::NOTAKTO_BOARDS },
+        Rules::Numerical,
+    ];
+
+    /// Parses `standard`, `misere`, `wild`, `notakto[@BOARDS]` or `numerical`.
+    pub fn parse(spec: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        match spec.split_once('@') {
+            None if spec == "standard" => Ok(Rules::Standard),
+            None if spec == "misere" || spec == "misère" => Ok(Rules::Misere),
+            None if spec == "wild" => Ok(Rules::Wild),
+            None if spec == "notakto" => Ok(Rules::Notakto { boards: Self::NOTAKTO_BOARDS }),
+            None if spec == "numerical" => Ok(Rules::Numerical),
+            Some(("notakto", boards)) => match boards.parse()? {
+                boards @ 1..=Self::MAX_NOTAKTO_BOARDS => Ok(Rules::Notakto { boards }),
+                boards => Err(format!("notakto is played on 1 to {} boards, not {}", Self::MAX_NOTAKTO_BOARDS, boards).into()),
+            },
+            _ => Err(format!("unknown rules '{}'", spec).into()),
+        }
+    }
+
+    /// Fails if the rules cannot be played on boards of `size`: the sums of numerical
+    /// tic-tac-toe only work out on the 3×3 board.
+    pub fn check_board(&self, size: BoardSize) -> Result<(), String> {
+        match self {
+            Rules::Numerical if size != BoardSize::CLASSIC => {
+                Err(format!("numerical tic-tac-toe is played on 3x3, not {}", size))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rules::Standard => write!(f, "Standard"),
+            Rules::Misere => write!(f, "Misère"),
+            Rules::Wild => write!(f, "Wild"),
+            Rules::Notakto { boards: 1 } => write!(f, "Notakto"),
+            Rules::Notakto { boards } => write!(f, "Notakto on {} boards", boards),
+            Rules::Numerical => write!(f, "Numerical"),
+        }
+    }
+}
+
+/// What a move places.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Piece {
+    Mark(Cell),
+    Number(u8),
+}
+
+impl fmt::Display for Piece {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Piece::Mark(marker) => write!(f, "{}", marker),
+            Piece::Number(number) => write!(f, "{}", number),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RulesMove {
+    /// Always 0 but in Notakto.
+    pub board: usize,
+    pub row: usize,
+    pub col: usize,
+    pub piece: Piece,
+}
+
+impl fmt::Display for RulesMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{},{}", self.board, self.row, self.col, self.piece)
+    }
+}
+
+/// A game under any `Rules`.
+#[derive(Debug, Clone)]
+pub struct RulesGame {
+    rules: Rules,
+    /// One board, or one per board of Notakto. In numerical tic-tac-toe the board holds the
+    /// marker of the player who placed each number.
+    boards: Vec<Board>,
+    /// The numbers of numerical tic-tac-toe, row by row, with 0 for an empty cell.
+    numbers: [u8; 9],
+    pub players: [Player; 2],
+    pub current_player: usize,
+    /// The player who moved first, who has the odd numbers.
+    first_player: usize,
+    winner: Option<Cell>,
+    game_over: bool,
+}
+
+impl RulesGame {
+    /// Starts a game on boards of `size`, which must suit the rules (see `Rules::check_board`).
+    pub fn new(rules: Rules, size: BoardSize) -> Self {
+        debug_assert!(rules.check_board(size).is_ok());
+        let boards = match rules {
+            Rules::Notakto { boards } => boards,
+            _ => 1,
+        };
+        let first_player = random::rng().random_range(0..=1);
+        RulesGame {
+            rules,
+            boards: vec![Board::with_size(size); boards],
+            numbers: [0; 9],
+            players: [Player::new(Cell::X), Player::new(Cell::O)],
+            current_player: first_player,
+            first_player,
+            winner: None,
+            game_over: false,
+        }
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    pub fn size(&self) -> BoardSize {
+        self.boards[0].size()
+    }
+
+    /// The number of boards, more than one only in Notakto.
+    pub fn boards(&self) -> usize {
+        self.boards.len()
+    }
+
+    pub fn get_current_player(&self) -> &Player {
+        &self.players[self.current_player]
+    }
+
+    /// The marker on a cell; in numerical tic-tac-toe, that of the player who placed the number.
+    pub fn cell(&self, board: usize, row: usize, col: usize) -> Cell {
+        self.boards[board].cell(row, col)
+    }
+
+    /// What has been placed on a cell, if anything.
+    pub fn piece(&self, board: usize, row: usize, col: usize) -> Option<Piece> {
+        match self.boards[board].cell(row, col) {
+            Cell::Empty => None,
+            _ if self.rules == Rules::Numerical => Some(Piece::Number(self.numbers[row * 3 + col])),
+            marker => Some(Piece::Mark(marker)),
+        }
+    }
+
+    /// Whether a board is still played on. In Notakto a board with a line is dead; the others
+    /// last as long as the game.
+    pub fn is_live(&self, board: usize) -> bool {
+        match self.rules {
+            Rules::Notakto { .. } => !self.boards[board].is_game_over().0,
+            _ => !self.game_over,
+        }
+    }
+
+    /// The pieces the current player may place.
+    pub fn pieces(&self) -> Vec<Piece> {
+        let marker = self.get_current_player().marker;
+        match self.rules {
+            Rules::Standard | Rules::Misere => vec![Piece::Mark(marker)],
+            Rules::Wild => vec![Piece::Mark(Cell::X), Piece::Mark(Cell::O)],
+            Rules::Notakto { .. } => vec![Piece::Mark(Cell::X)],
+            Rules::Numerical => {
+                let first = if self.current_player == self.first_player { 1 } else { 2 };
+                (first..=9)
+                    .step_by(2)
+                    .filter(|number| !self.numbers.contains(number))
+                    .map(Piece::Number)
+                    .collect()
+            }
+        }
+    }
+
+    pub fn available_moves(&self) -> Vec<RulesMove> {
+        if self.game_over {
+            return Vec::new();
+        }
+        let pieces = self.pieces();
+        (0..self.boards.len())
+            .filter(|&board| self.is_live(board))
+            .flat_map(|board| {
+                let pieces = &pieces;
+                self.boards[board].available_moves().into_iter().flat_map(move |(row, col)| {
+                    pieces.iter().map(move |&piece| RulesMove { board, row, col, piece })
+                })
+            })
+            .collect()
+    }
+
+    pub fn make_move(&mut self, action: RulesMove) -> (MoveStatus, Option<Cell>) {
+        let RulesMove { board, row, col, piece } = action;
+        let size = self.size();
+        if board >= self.boards.len()
+            || row >= size.height
+            || col >= size.width
+            || !self.is_live(board)
+            || self.boards[board].cell(row, col) != Cell::Empty
+            || !self.pieces().contains(&piece)
+        {
+            return (MoveStatus::new(false, false), None);
+        }
+        let mover = self.get_current_player().marker;
+        let marker = match piece {
+            Piece::Mark(marker) => marker,
+            Piece::Number(number) => {
+                self.numbers[row * 3 + col] = number;
+                mover
+            }
+        };
+        self.boards[board].place(row, col, marker);
+        let completed = match self.rules {
+            Rules::Numerical => self.completes_sum(row * 3 + col),
+            _ => self.boards[board].check_winner().is_some(),
+        };
+        let opponent = self.get_current_player().opponent().marker;
+        self.winner = match self.rules {
+            Rules::Standard | Rules::Wild | Rules::Numerical if completed => Some(mover),
+            Rules::Misere if completed => Some(opponent),
+            // whoever plays the last move on the last live board loses
+            Rules::Notakto { .. } if (0..self.boards.len()).all(|board| !self.is_live(board)) => Some(opponent),
+            _ => None,
+        };
+        self.game_over = self.winner.is_some() || self.boards.iter().all(Board::is_draw);
+        self.switch_turn();
+        (MoveStatus::new(true, self.game_over), self.winner)
+    }
+
+    /// Whether the number just placed at `cell` completes a line summing to 15.
+    fn completes_sum(&self, cell: usize) -> bool {
+        LINES.iter().filter(|line| line.contains(&cell)).any(|line| {
+            line.iter().all(|&cell| self.numbers[cell] != 0) && line.iter().map(|&cell| self.numbers[cell]).sum::<u8>() == 15
+        })
+    }
+
+    pub fn switch_turn(&mut self) {
+        self.current_player = 1 - self.current_player;
+    }
+
+    pub fn check_winner(&self) -> Option<Cell> {
+        self.winner
+    }
+
+    pub fn is_game_over(&self) -> (bool, Option<Cell>) {
+        (self.game_over, self.winner)
+    }
+
+    /// The Q-table key of the position: the boards separated by `|`, with the numbers of
+    /// numerical tic-tac-toe in place of markers. Only where the players place their own
+    /// markers does the side to move follow, after a `:`; in the other variants either player
+    /// would have the same moves.
+    pub fn board_state(&self) -> String {
+        match self.rules {
+            Rules::Standard | Rules::Misere => format!("{}:{}", self.boards[0].board_state(), self.get_current_player().marker),
+            Rules::Numerical => self
+                .numbers
+                .iter()
+                .map(|&number| if number == 0 { '-' } else { char::from(b'0' + number) })
+                .collect(),
+            _ => self.boards.iter().map(Board::board_state).collect::<Vec<_>>().join("|"),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = RulesGame::new(self.rules, self.size());
+    }
+}
+
+impl fmt::Display for RulesGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.board_state())
+    }
+}
+
+/// The agent's value of `action` in `state`; moves it has not learned are worth 0.
+fn value(agent: &QLearningAgent, state: &str, action: &RulesMove) -> f64 {
+    agent
+        .q_table
+        .get(state)
+        .and_then(|actions| actions.get(&action.to_string()))
+        .map_or(0.0, |entry| entry.value)
+}
+
+/// Picks a move for the current player: a random one with probability epsilon while the agent
+/// trains, otherwise the highest valued, breaking ties at random. Returns whether it explored.
+pub fn choose_move(agent: &QLearningAgent, game: &RulesGame) -> (RulesMove, bool) {
+    let mut rng = random::rng();
+    let moves = game.available_moves();
+    if agent.train && rng.random::<f64>() < agent.epsilon {
+        return (*moves.choose(&mut rng).unwrap(), true);
+    }
+    let state = game.board_state();
+    let values: Vec<f64> = moves.iter().map(|action| value(agent, &state, action)).collect();
+    let best = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let best_moves: Vec<RulesMove> =
+        moves.into_iter().zip(values).filter(|&(_, value)| value == best).map(|(action, _)| action).collect();
+    (*best_moves.choose(&mut rng).unwrap(), false)
+}
+
+/// Plays one episode with the agent on both sides. Values are from the point of view of the
+/// player to move, so a move is worth its result once the game is over, and otherwise minus
+/// the discounted value of the opponent's best reply.
+pub(crate) fn self_play_episode(agent: &mut QLearningAgent, mut game: RulesGame) -> EpisodeStats {
+    let mut stats = EpisodeStats::default();
+    while !game.is_game_over().0 {
+        let state = game.board_state();
+        let mover = game.get_current_player().marker;
+        let (action, explore) = choose_move(agent, &game);
+        stats.record_move(explore, false);
+        let (_, winner) = game.make_move(action);
+        let target = match game.is_game_over() {
+            (true, Some(winner)) if winner == mover => 1.0,
+            (true, Some(_)) => -1.0,
+            (true, None) => 0.0,
+            (false, _) => {
+                let next_state = game.board_state();
+                let best_reply = game
+                    .available_moves()
+                    .iter()
+                    .map(|reply| value(agent, &next_state, reply))
+                    .fold(f64::NEG_INFINITY, f64::max);
+                -agent.gamma * best_reply
+            }
+        };
+        agent.update_towards(&state, &action.to_string(), target);
+        stats.winner = winner;
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A game of `rules` on the 3×3 board in which X moves first.
+    fn game(rules: Rules) -> RulesGame {
+        let mut game = RulesGame::new(rules, BoardSize::CLASSIC);
+        (game.current_player, game.first_player) = (0, 0);
+        game
+    }
+
+    fn mark(board: usize, row: usize, col: usize, marker: Cell) -> RulesMove {
+        RulesMove { board, row, col, piece: Piece::Mark(marker) }
+    }
+
+    fn number(row: usize, col: usize, number: u8) -> RulesMove {
+        RulesMove { board: 0, row, col, piece: Piece::Number(number) }
+    }
+
+    /// Plays `moves`, which must all be legal, and returns the result.
+    fn play(game: &mut RulesGame, moves: &[RulesMove]) -> (bool, Option<Cell>) {
+        for &action in moves {
+            assert!(game.make_move(action).0.move_successful, "{} was refused", action);
+        }
+        game.is_game_over()
+    }
+
+    #[test]
+    fn misere_line_loses() {
+        let mut misere = game(Rules::Misere);
+        let moves = [mark(0, 0, 0, Cell::X), mark(0, 1, 0, Cell::O), mark(0, 0, 1, Cell::X), mark(0, 2, 2, Cell::O)];
+        assert_eq!(play(&mut misere, &moves), (false, None));
+        assert_eq!(play(&mut misere, &[mark(0, 0, 2, Cell::X)]), (true, Some(Cell::O)));
+    }
+
+    #[test]
+    fn wild_line_of_either_marker_wins() {
+        let mut wild = game(Rules::Wild);
+        assert_eq!(play(&mut wild, &[mark(0, 0, 0, Cell::O), mark(0, 0, 1, Cell::O)]), (false, None));
+        assert_eq!(play(&mut wild, &[mark(0, 0, 2, Cell::O)]), (true, Some(Cell::X)));
+    }
+
+    #[test]
+    fn notakto_killing_the_last_board_loses() {
+        let mut notakto = game(Rules::Notakto { boards: 2 });
+        // X kills the first board, which can no longer be played on
+        let first = [mark(0, 0, 0, Cell::X), mark(0, 0, 1, Cell::X), mark(0, 0, 2, Cell::X)];
+        assert_eq!(play(&mut notakto, &first), (false, None));
+        assert!(!notakto.is_live(0) && notakto.is_live(1));
+        assert!(!notakto.make_move(mark(0, 1, 1, Cell::X)).0.move_successful);
+        assert!(notakto.available_moves().iter().all(|action| action.board == 1));
+        // O kills the last one
+        let second = [mark(1, 0, 0, Cell::X), mark(1, 1, 1, Cell::X), mark(1, 2, 2, Cell::X)];
+        assert_eq!(play(&mut notakto, &second), (true, Some(Cell::X)));
+    }
+
+    #[test]
+    fn numerical_line_summing_to_15_wins() {
+        let mut numerical = game(Rules::Numerical);
+        // a full line that sums to something else wins nothing
+        assert_eq!(play(&mut numerical, &[number(1, 0, 1), number(1, 1, 2), number(1, 2, 3)]), (false, None));
+        assert_eq!(play(&mut numerical, &[number(0, 0, 8), number(0, 1, 5), number(2, 2, 4)]), (false, None));
+        assert_eq!(play(&mut numerical, &[number(0, 2, 9)]), (false, None));
+        // 1 + 8 + 6 completes the top row, for the second player, who placed the 6
+        let mut numerical = game(Rules::Numerical);
+        let moves = [number(0, 0, 1), number(0, 1, 8), number(1, 1, 9), number(2, 2, 4), number(1, 0, 3)];
+        assert_eq!(play(&mut numerical, &moves), (false, None));
+        assert_eq!(play(&mut numerical, &[number(0, 2, 6)]), (true, Some(Cell::O)));
+    }
+
+    #[test]
+    fn numerical_numbers_are_used_once() {
+        let mut numerical = game(Rules::Numerical);
+        play(&mut numerical, &[number(0, 0, 5), number(1, 1, 4)]);
+        assert!(!numerical.make_move(number(2, 2, 5)).0.move_successful);
+        assert!(!numerical.pieces().contains(&Piece::Number(5)));
+        assert_eq!(numerical.pieces(), [1, 3, 7, 9].map(Piece::Number));
+    }
+
+    #[test]
+    fn rules_cannot_be_mixed() {
+        let refused = [
+            (Rules::Standard, number(0, 0, 1)),
+            (Rules::Standard, mark(0, 0, 0, Cell::O)),
+            (Rules::Misere, mark(0, 0, 0, Cell::O)),
+            (Rules::Notakto { boards: 2 }, mark(0, 0, 0, Cell::O)),
+            (Rules::Notakto { boards: 2 }, mark(2, 0, 0, Cell::X)),
+            (Rules::Numerical, mark(0, 0, 0, Cell::X)),
+            (Rules::Numerical, number(0, 0, 2)),
+            (Rules::Wild, number(0, 0, 1)),
+        ];
+        for (rules, action) in refused {
+            let mut game = game(rules);
+            assert!(!game.make_move(action).0.move_successful, "{} accepted {}", rules, action);
+            assert_eq!(game.get_current_player().marker, Cell::X);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+use crate::curriculum::reachable_positions;
+use crate::rules::Rules;
+use crate::{Board, BoardSize, QLearningAgent};
+
+const VISIT_BUCKETS: [(u64, u64); 6] = [(0, 0), (1, 1), (2, 9), (10, 99), (100, 999), (1000, u64::MAX)];
+
+/// Prints which positions the agent has actually experienced and how often.
+pub fn print_coverage(agent: &QLearningAgent) {
+    let entries: Vec<_> = agent.q_table.values().flat_map(|actions| actions.values()).collect();
+    let updates: u64 = entries.iter().map(|entry| entry.visits).sum();
+    println!("States: {}, entries: {}, updates: {}", agent.q_table.len(), entries.len(), updates);
+
+    println!("Entries by visit count:");
+    for (low, high) in VISIT_BUCKETS {
+        let count = entries.iter().filter(|entry| (low..=high).contains(&entry.visits)).count();
+        let label = match (low, high) {
+            (low, high) if low == high => low.to_string(),
+            (low, u64::MAX) => format!("{}+", low),
+            (low, high) => format!("{}-{}", low, high),
+        };
+        println!("  {:>9}: {}", label, count);
+    }
+
+    // only the positions of the 3×3 board are few enough to list, and only the standard
+    // rules key them as listed here
+    if agent.metadata.board != BoardSize::CLASSIC || agent.metadata.rules != Rules::Standard {
+        return;
+    }
+    // a position counts as experienced once any of its moves has been learned from
+    let experienced = |state: &str| {
+        agent
+            .q_table
+            .get(state)
+            .is_some_and(|actions| actions.values().any(|entry| entry.visits > 0))
+    };
+    let mut by_pieces = [(0usize, 0usize); 9];
+    for state in std::iter::once(Board::new().board_state()).chain(reachable_positions()) {
+        let pieces = state.chars().filter(|&c| c != '-').count();
+        by_pieces[pieces].1 += 1;
+        if experienced(&state) {
+            by_pieces[pieces].0 += 1;
+        }
+    }
+    let (seen, total) = by_pieces.iter().fold((0, 0), |(seen, total), &(s, t)| (seen + s, total + t));
+    println!("Reachable positions experienced: {}/{} ({:.1}%)", seen, total, 100.0 * seen as f64 / total as f64);
+    for (pieces, (seen, total)) in by_pieces.iter().enumerate() {
+        println!("  {} pieces: {}/{}", pieces, seen, total);
+    }
+}
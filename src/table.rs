@@ -0,0 +1,262 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! Combining and comparing the Q-tables of separately trained agents.
+
+use crate::{Board, QEntry, QLearningAgent};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergeStrategy {
+    /// The mean over all the agents, counting a missing entry as 0, the value an agent plays
+    /// it with, so that an entry only one agent has seen does not outweigh the others.
+    Mean,
+    /// The largest value of any agent that has the entry.
+    Max,
+    /// The mean weighted by how often each agent updated the entry.
+    VisitWeighted,
+}
+
+impl MergeStrategy {
+    /// Parses `mean`, `max` or `visits`.
+    pub fn parse(spec: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        match spec {
+            "mean" => Ok(MergeStrategy::Mean),
+            "max" => Ok(MergeStrategy::Max),
+            "visits" => Ok(MergeStrategy::VisitWeighted),
+            _ => Err(format!("unknown merge strategy '{}'", spec).into()),
+        }
+    }
+}
+
+/// Fails unless `other` was trained on the board and under the rules of `first`, so that the
+/// states and actions of their tables mean the same.
+fn check_compatible(first: &QLearningAgent, other: &QLearningAgent) -> Result<(), String> {
+    if other.metadata.board != first.metadata.board {
+        return Err(format!("cannot combine tables of a {} board and a {} board", first.metadata.board, other.metadata.board));
+    }
+    if other.metadata.rules != first.metadata.rules {
+        return Err(format!("cannot combine tables of {} and {} rules", first.metadata.rules, other.metadata.rules));
+    }
+    Ok(())
+}
+
+/// Merges the tables of `agents` into a new agent with the hyperparameters, board and rules of
+/// the first. The agents must have been trained on the same board under the same rules.
+///
+/// Every entry of any agent ends up in the result. Visits are summed, so the merged agent
+/// counts as having played the episodes of all of them.
+pub fn merge(agents: &[QLearningAgent], strategy: MergeStrategy) -> Result<QLearningAgent, String> {
+    let first = agents.first().ok_or("no models to merge")?;
+    agents.iter().try_for_each(|agent| check_compatible(first, agent))?;
+    let mut merged = QLearningAgent::new(first.alpha, first.gamma, first.epsilon);
+    merged.alpha_schedule = first.alpha_schedule;
+    merged.exploration = first.exploration;
+    merged.metadata.initial_epsilon = first.metadata.initial_epsilon;
+    merged.metadata.board = first.metadata.board;
+    merged.metadata.rules = first.metadata.rules;
+    merged.metadata.episodes = agents.iter().map(|agent| agent.metadata.episodes).sum();
+
+    let mut entries: HashMap<&str, HashMap<&str, Vec<&QEntry>>> = HashMap::new();
+    for agent in agents {
+        for (state, actions) in &agent.q_table {
+            let merged_actions = entries.entry(state.as_str()).or_default();
+            for (action, entry) in actions {
+                merged_actions.entry(action.as_str()).or_default().push(entry);
+            }
+        }
+    }
+    for (state, actions) in entries {
+        let actions = actions
+            .into_iter()
+            .map(|(action, entries)| (action.to_string(), merge_entries(&entries, agents.len(), strategy)))
+            .collect();
+        merged.q_table.insert(state.to_string(), actions);
+    }
+    Ok(merged)
+}
+
+/// Merges the entries the agents that have one hold for the same action, out of `agents` agents.
+fn merge_entries(entries: &[&QEntry], agents: usize, strategy: MergeStrategy) -> QEntry {
+    let count = entries.len() as f64;
+    let visits: u64 = entries.iter().map(|entry| entry.visits).sum();
+    let mean = entries.iter().map(|entry| entry.value).sum::<f64>() / agents as f64;
+    let value = match strategy {
+        MergeStrategy::Mean => mean,
+        MergeStrategy::Max => entries.iter().map(|entry| entry.value).fold(f64::NEG_INFINITY, f64::max),
+        // entries nobody learned from carry no weight, unless that is all of them
+        MergeStrategy::VisitWeighted if visits == 0 => mean,
+        MergeStrategy::VisitWeighted => {
+            entries.iter().map(|entry| entry.value * entry.visits as f64).sum::<f64>() / visits as f64
+        }
+    };
+    QEntry {
+        value,
+        visits,
+        last_episode: entries.iter().map(|entry| entry.last_episode).max().unwrap_or(0),
+        td_error: entries.iter().map(|entry| entry.td_error).sum::<f64>() / count,
+    }
+}
+
+/// A state where the two agents would play differently.
+#[derive(Debug, Clone)]
+pub struct Disagreement {
+    pub state: String,
+    pub left: (usize, usize),
+    pub right: (usize, usize),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TableDiff {
+    pub only_left: Vec<String>,
+    pub only_right: Vec<String>,
+    /// States in both tables, which `disagreements` and `differences` are taken over.
+    pub shared: usize,
+    pub disagreements: Vec<Disagreement>,
+    /// `(state, action, left - right)` for entries in both tables, largest difference first.
+    pub differences: Vec<(String, String, f64)>,
+    /// How many disagreements and differences `Display` lists.
+    pub limit: usize,
+}
+
+/// Compares the tables of two agents, which must have been trained on the same board under
+/// the same rules.
+pub fn diff(left: &QLearningAgent, right: &QLearningAgent) -> Result<TableDiff, String> {
+    check_compatible(left, right)?;
+    let states: BTreeSet<&String> = left.q_table.keys().chain(right.q_table.keys()).collect();
+    let mut diff = TableDiff { limit: 10, ..TableDiff::default() };
+    for state in states {
+        let (left_actions, right_actions) = match (left.q_table.get(state), right.q_table.get(state)) {
+            (Some(left_actions), Some(right_actions)) => (left_actions, right_actions),
+            (Some(_), None) => {
+                diff.only_left.push(state.clone());
+                continue;
+            }
+            _ => {
+                diff.only_right.push(state.clone());
+                continue;
+            }
+        };
+        diff.shared += 1;
+        let moves = Board::from_state_with_size(left.metadata.board, state).map(|board| board.available_moves()).unwrap_or_default();
+        if let (Some(left_move), Some(right_move)) = (left.greedy_action(state, &moves), right.greedy_action(state, &moves))
+            && left_move != right_move
+        {
+            diff.disagreements.push(Disagreement { state: state.clone(), left: left_move, right: right_move });
+        }
+        for (action, left_entry) in left_actions {
+            if let Some(right_entry) = right_actions.get(action) {
+                diff.differences.push((state.clone(), action.clone(), left_entry.value - right_entry.value));
+            }
+        }
+    }
+    diff.differences.sort_by(|a, b| b.2.abs().total_cmp(&a.2.abs()));
+    Ok(diff)
+}
+
+impl fmt::Display for TableDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "States: {} shared, {} only in the first table, {} only in the second",
+            self.shared,
+            self.only_left.len(),
+            self.only_right.len()
+        )?;
+        writeln!(f, "Greedy moves differ in {} of {} shared states", self.disagreements.len(), self.shared)?;
+        for disagreement in self.disagreements.iter().take(self.limit) {
+            writeln!(f, "  {}: {:?} vs {:?}", disagreement.state, disagreement.left, disagreement.right)?;
+        }
+        writeln!(f, "Largest value differences:")?;
+        for (state, action, difference) in self.differences.iter().take(self.limit) {
+            writeln!(f, "  {} {}: {:+.4}", state, action, difference)?;
+        }
+        for (label, states) in [("Only in the first", &self.only_left), ("Only in the second", &self.only_right)] {
+            if !states.is_empty() {
+                let shown: Vec<_> = states.iter().take(self.limit).map(String::as_str).collect();
+                let more = states.len().saturating_sub(self.limit);
+                write!(f, "{}: {}", label, shown.join(" "))?;
+                if more > 0 {
+                    write!(f, " and {} more", more)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rules;
+    use crate::BoardSize;
+
+    fn agent_with(entries: &[(&str, &str, f64, u64)]) -> QLearningAgent {
+        let mut agent = QLearningAgent::new(0.1, 0.9, 0.5);
+        for &(state, action, value, visits) in entries {
+            let entry = QEntry { value, visits, ..QEntry::default() };
+            agent.q_table.entry(state.to_string()).or_default().insert(action.to_string(), entry);
+        }
+        agent
+    }
+
+    fn value(agent: &QLearningAgent, state: &str, action: &str) -> f64 {
+        agent.q_table[state][action].value
+    }
+
+    #[test]
+    fn mean_counts_missing_entries_as_zero() {
+        let agents = [
+            agent_with(&[("---------", "1,1", 0.6, 10), ("X--------", "1,1", 0.9, 1)]),
+            agent_with(&[("---------", "1,1", 0.2, 30)]),
+        ];
+        let merged = merge(&agents, MergeStrategy::Mean).unwrap();
+        assert!((value(&merged, "---------", "1,1") - 0.4).abs() < 1e-12);
+        assert!((value(&merged, "X--------", "1,1") - 0.45).abs() < 1e-12);
+        assert_eq!(merged.q_table["---------"]["1,1"].visits, 40);
+
+        let merged = merge(&agents, MergeStrategy::Max).unwrap();
+        assert_eq!(value(&merged, "---------", "1,1"), 0.6);
+        assert_eq!(value(&merged, "X--------", "1,1"), 0.9);
+
+        let merged = merge(&agents, MergeStrategy::VisitWeighted).unwrap();
+        assert!((value(&merged, "---------", "1,1") - 0.3).abs() < 1e-12);
+        assert_eq!(value(&merged, "X--------", "1,1"), 0.9);
+    }
+
+    #[test]
+    fn merge_keeps_the_board_of_its_inputs() {
+        let mut agents = [agent_with(&[("----------------", "1,1", 0.5, 1)]), agent_with(&[("----------------", "2,2", 0.5, 1)])];
+        let board = BoardSize::parse("4x4:3").unwrap();
+        agents.iter_mut().for_each(|agent| agent.metadata.board = board);
+        let merged = merge(&agents, MergeStrategy::Mean).unwrap();
+        assert_eq!(merged.metadata.board, board);
+        assert_eq!(merged.q_table["----------------"].len(), 2);
+        assert!(diff(&agents[0], &agents[1]).is_ok());
+
+        agents[1].metadata.board = BoardSize::CLASSIC;
+        let error = merge(&agents, MergeStrategy::Mean).unwrap_err();
+        assert_eq!(error, "cannot combine tables of a 4x4:3 board and a 3x3:3 board");
+        assert!(diff(&agents[0], &agents[1]).is_err());
+        assert!(merge(&[], MergeStrategy::Mean).is_err());
+    }
+
+    #[test]
+    fn merge_keeps_the_rules_of_its_inputs() {
+        let mut agents = [agent_with(&[("---------", "1,1", 0.5, 1)]), agent_with(&[("---------", "0,0", 0.5, 1)])];
+        agents.iter_mut().for_each(|agent| agent.metadata.rules = Rules::Misere);
+        let merged = merge(&agents, MergeStrategy::Max).unwrap();
+        assert_eq!(merged.metadata.rules, Rules::Misere);
+        assert!(diff(&agents[0], &agents[1]).is_ok());
+
+        agents[1].metadata.rules = Rules::Standard;
+        let error = merge(&agents, MergeStrategy::Max).unwrap_err();
+        assert_eq!(error, "cannot combine tables of Misère and Standard rules");
+        assert!(diff(&agents[0], &agents[1]).is_err());
+    }
+}
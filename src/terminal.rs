@@ -0,0 +1,113 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! Playing in a terminal, for machines without a display.
+
+use crate::{Board, BoardSize, Cell, GameMode, QLearningAgent};
+use std::io::{self, BufRead, Write};
+
+/// Plays games on a board of `size` on `input` and `output` until the player quits or the
+/// input ends. In PvA mode `agent` plays `ai_side` the same way as in the GUI.
+pub fn play(
+    mode: GameMode,
+    mut agent: QLearningAgent,
+    ai_side: Cell,
+    size: BoardSize,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    agent.set_training(false);
+    loop {
+        let mut board = Board::with_size(size);
+        writeln!(output, "{} starts.", board.get_current_player().marker)?;
+        let (_, winner) = loop {
+            let (over, winner) = board.is_game_over();
+            if over {
+                break (over, winner);
+            }
+            write_board(output, &board)?;
+            let marker = board.get_current_player().marker;
+            if mode == GameMode::PvA && marker == ai_side {
+                let state = board.board_state();
+                let blocking_move = board.find_blocking_move();
+                let ((row, col), _, _) = agent.choose_action(&state, &board.available_moves(), blocking_move);
+                board.make_move(row, col);
+                writeln!(output, "{} plays {},{}", marker, row, col)?;
+                continue;
+            }
+            write!(output, "{} to move (1-{} or row,col; q quits): ", marker, size.cells())?;
+            output.flush()?;
+            let Some(line) = read_line(input)? else { return Ok(()) };
+            if line == "q" || line == "quit" {
+                return Ok(());
+            }
+            match parse_move(&line, size) {
+                Some((row, col)) if board.make_move(row, col).0.move_successful => {}
+                Some(_) => writeln!(output, "That cell is taken.")?,
+                None => writeln!(
+                    output,
+                    "Enter a cell number from 1 to {}, or row,col from 0,0 to {},{}.",
+                    size.cells(),
+                    size.height - 1,
+                    size.width - 1
+                )?,
+            }
+        };
+        write_board(output, &board)?;
+        match winner {
+            Some(winner) => writeln!(output, "{} wins!", winner)?,
+            None => writeln!(output, "It's a draw!")?,
+        }
+        write!(output, "Play again? [y/N] ")?;
+        output.flush()?;
+        if !matches!(read_line(input)?.as_deref(), Some("y" | "yes")) {
+            return Ok(());
+        }
+    }
+}
+
+fn read_line(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_lowercase()))
+}
+
+/// Accepts a cell number, counted from 1 row by row, or zero-based `row,col` coordinates.
+fn parse_move(input: &str, size: BoardSize) -> Option<(usize, usize)> {
+    let (row, col) = match input.split_once([',', ' ']) {
+        Some((row, col)) => (row.trim().parse().ok()?, col.trim().parse().ok()?),
+        None => {
+            let cell = input.parse::<usize>().ok()?.checked_sub(1)?;
+            (cell / size.width, cell % size.width)
+        }
+    };
+    (row < size.height && col < size.width).then_some((row, col))
+}
+
+/// Draws the grid with the number of each empty cell in its place.
+fn write_board(output: &mut impl Write, board: &Board) -> io::Result<()> {
+    let size = board.size();
+    let label_width = size.cells().to_string().len();
+    let separator = vec!["-".repeat(label_width + 2); size.width].join("+");
+    writeln!(output)?;
+    for (row, cells) in board.rows().enumerate() {
+        let labels: Vec<String> = cells
+            .iter()
+            .enumerate()
+            .map(|(col, cell)| match cell {
+                Cell::Empty => format!("{:>label_width$}", row * size.width + col + 1),
+                marker => format!("{:>label_width$}", marker),
+            })
+            .collect();
+        writeln!(output, " {} ", labels.join(" | "))?;
+        if row + 1 < size.height {
+            writeln!(output, "{}", separator)?;
+        }
+    }
+    writeln!(output)
+}
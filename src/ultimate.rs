@@ -0,0 +1,283 @@
+// Copyright (c) 2025 Krishbin Paudel krishbinp@outlook.com
+// SPDX-License-Identifier: MIT
+//
+// This file is part of krishbin/q-learning-tic-tac-toe and is licensed under the MIT or Apache 2.0 license.
+// See the LICENSE file for details.
+
+//! Ultimate tic-tac-toe: nine local boards arranged in a 3×3 meta-board.
+//!
+//! The cell a player picks on a local board sends the opponent to the local board in the same
+//! position. Winning a local board claims its square of the meta-board, and three claimed
+//! squares in a row win the game. A local board that is won or full is closed; a player sent
+//! to a closed board may play on any open one.
+//!
+//! Moves are `(row, col)` on the whole 9×9 grid. The local boards and the meta-board are
+//! ordinary `Board`s, so they keep the usual move and win rules.
+
+use crate::{random, Board, Cell, MoveStatus, Player, LINES};
+use rand::Rng;
+use rand::prelude::IndexedRandom;
+use std::fmt;
+
+/// Scores beyond any heuristic evaluation; faster wins score higher.
+const WIN_SCORE: i32 = 1_000_000;
+
+#[derive(Debug, Clone)]
+pub struct UltimateBoard {
+    /// The local boards, row by row.
+    boards: [Board; 9],
+    /// Holds the winner of each local board.
+    meta: Board,
+    pub players: [Player; 2],
+    pub current_player: usize,
+    /// The local board the next move must be played on, if it is open.
+    forced: Option<usize>,
+}
+
+/// Splits a move on the 9×9 grid into its local board and the row and column on it.
+pub fn local(row: usize, col: usize) -> (usize, usize, usize) {
+    (row / 3 * 3 + col / 3, row % 3, col % 3)
+}
+
+impl UltimateBoard {
+    pub fn new() -> Self {
+        UltimateBoard {
+            boards: std::array::from_fn(|_| Board::new()),
+            meta: Board::new(),
+            players: [Player::new(Cell::X), Player::new(Cell::O)],
+            current_player: random::rng().random_range(0..=1),
+            forced: None,
+        }
+    }
+
+    pub fn get_current_player(&self) -> &Player {
+        &self.players[self.current_player]
+    }
+
+    pub fn cell(&self, row: usize, col: usize) -> Cell {
+        let (board, row, col) = local(row, col);
+        self.boards[board].cell(row, col)
+    }
+
+    /// The winner of a local board.
+    pub fn local_winner(&self, board: usize) -> Option<Cell> {
+        self.boards[board].check_winner()
+    }
+
+    /// Whether a local board is still being played, won by no one and not full.
+    pub fn is_open(&self, board: usize) -> bool {
+        !self.boards[board].is_game_over().0
+    }
+
+    /// The local board the next move must be played on, or `None` if any open board will do.
+    pub fn forced_board(&self) -> Option<usize> {
+        self.forced
+    }
+
+    /// Whether the next move may be played on a local board.
+    pub fn is_playable(&self, board: usize) -> bool {
+        !self.is_game_over().0 && self.is_open(board) && self.forced.is_none_or(|forced| forced == board)
+    }
+
+    pub fn make_move(&mut self, row: usize, col: usize) -> (MoveStatus, Option<Cell>) {
+        if row >= 9 || col >= 9 {
+            return (MoveStatus::new(false, false), None);
+        }
+        let (board, local_row, local_col) = local(row, col);
+        if !self.is_playable(board) {
+            return (MoveStatus::new(false, false), None);
+        }
+        let local = &mut self.boards[board];
+        local.current_player = self.current_player;
+        let (status, local_winner) = local.make_move(local_row, local_col);
+        if !status.move_successful {
+            return (status, None);
+        }
+        if local_winner.is_some() {
+            self.meta.current_player = self.current_player;
+            self.meta.make_move(board / 3, board % 3);
+        }
+        let next = local_row * 3 + local_col;
+        self.forced = self.is_open(next).then_some(next);
+        self.switch_turn();
+        let (game_over, winner) = self.is_game_over();
+        (MoveStatus::new(true, game_over), winner)
+    }
+
+    /// A move that wins the game for the current player: one that wins its local board when
+    /// that board completes a line of the meta-board.
+    pub fn find_winning_move(&self) -> Option<(usize, usize)> {
+        let marker = self.get_current_player().marker;
+        self.available_moves().into_iter().find(|&(row, col)| {
+            let (board, local_row, local_col) = local(row, col);
+            self.boards[board].completes_line(local_row, local_col, marker)
+                && self.meta.completes_line(board / 3, board % 3, marker)
+        })
+    }
+
+    pub fn switch_turn(&mut self) {
+        self.current_player = 1 - self.current_player;
+    }
+
+    pub fn check_winner(&self) -> Option<Cell> {
+        self.meta.check_winner()
+    }
+
+    pub fn is_game_over(&self) -> (bool, Option<Cell>) {
+        let winner = self.check_winner();
+        (winner.is_some() || (0..9).all(|board| !self.is_open(board)), winner)
+    }
+
+    /// The 81 cells of the grid row by row, followed by the forced board or `-`.
+    pub fn board_state(&self) -> String {
+        let mut state: String = (0..81).map(|i| self.cell(i / 9, i % 9).to_string()).collect();
+        state.push(self.forced.map_or('-', |board| char::from(b'0' + board as u8)));
+        state
+    }
+
+    pub fn available_moves(&self) -> Vec<(usize, usize)> {
+        (0..9)
+            .filter(|&board| self.is_playable(board))
+            .flat_map(|board| {
+                self.boards[board]
+                    .available_moves()
+                    .into_iter()
+                    .map(move |(row, col)| (board / 3 * 3 + row, board % 3 * 3 + col))
+            })
+            .collect()
+    }
+
+    pub fn reset(&mut self) {
+        *self = UltimateBoard::new();
+    }
+}
+
+impl Default for UltimateBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for UltimateBoard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.board_state())
+    }
+}
+
+/// A computer player for ultimate tic-tac-toe; the Q-learner's tables cannot cover its state space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UltimateOpponent {
+    /// Picks uniformly among the legal moves.
+    Random,
+    /// Alpha-beta search `depth` moves ahead, scoring the positions it stops at with `evaluate`.
+    Search { depth: usize },
+}
+
+impl UltimateOpponent {
+    pub const DEFAULT_DEPTH: usize = 4;
+
+    /// Parses `random` or `search[@DEPTH]`.
+    pub fn parse(kind: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        match kind.split_once('@') {
+            None if kind == "random" => Ok(UltimateOpponent::Random),
+            None if kind == "search" => Ok(UltimateOpponent::Search { depth: Self::DEFAULT_DEPTH }),
+            Some(("search", depth)) => match depth.parse()? {
+                0 => Err("search depth must be at least 1".into()),
+                depth => Ok(UltimateOpponent::Search { depth }),
+            },
+            _ => Err(format!("unknown ultimate opponent '{}'", kind).into()),
+        }
+    }
+
+    /// Picks a move for the current player; the game must not be over. Equally good moves are
+    /// chosen between at random.
+    pub fn choose_move(&self, game: &UltimateBoard) -> (usize, usize) {
+        let mut rng = random::rng();
+        let moves = game.available_moves();
+        match *self {
+            UltimateOpponent::Random => *moves.choose(&mut rng).unwrap(),
+            UltimateOpponent::Search { depth } => {
+                let scores: Vec<((usize, usize), i32)> = moves
+                    .into_iter()
+                    .map(|pos| {
+                        let mut child = game.clone();
+                        child.make_move(pos.0, pos.1);
+                        (pos, -negamax(&child, depth - 1, -WIN_SCORE * 2, WIN_SCORE * 2))
+                    })
+                    .collect();
+                let best_score = scores.iter().map(|&(_, score)| score).max().unwrap();
+                let best_moves: Vec<(usize, usize)> =
+                    scores.into_iter().filter(|&(_, score)| score == best_score).map(|(pos, _)| pos).collect();
+                *best_moves.choose(&mut rng).unwrap()
+            }
+        }
+    }
+}
+
+/// Scores `game` from the point of view of the player to move.
+fn negamax(game: &UltimateBoard, depth: usize, mut alpha: i32, beta: i32) -> i32 {
+    let (game_over, winner) = game.is_game_over();
+    if game_over {
+        // the previous player completed the meta-board, or no open board is left
+        return if winner.is_some() { -WIN_SCORE - depth as i32 } else { 0 };
+    }
+    if depth == 0 {
+        return evaluate(game, game.get_current_player().marker);
+    }
+    let mut best = -WIN_SCORE * 2;
+    for (row, col) in game.available_moves() {
+        let mut child = game.clone();
+        child.make_move(row, col);
+        best = best.max(-negamax(&child, depth - 1, -beta, -alpha));
+        alpha = alpha.max(best);
+        if alpha >= beta {
+            break;
+        }
+    }
+    best
+}
+
+/// A heuristic score of an unfinished game for `marker`: local boards won and lines of the
+/// meta-board still open to it count most, then two-in-a-rows on the open local boards, and
+/// the centre board and centre cells a little extra. The opponent's count against it.
+pub fn evaluate(game: &UltimateBoard, marker: Cell) -> i32 {
+    // scores a line by who owns each of its cells, or of its local boards on the meta-board
+    let line_score = |owners: [Option<Cell>; 3], weights: [i32; 3]| {
+        let mine = owners.iter().filter(|&&owner| owner == Some(marker)).count();
+        let theirs = owners.iter().filter(|&&owner| owner.is_some_and(|owner| owner != marker)).count();
+        match (mine, theirs) {
+            (mine, 0) => weights[mine.min(2)],
+            (0, theirs) => -weights[theirs.min(2)],
+            _ => 0,
+        }
+    };
+    let mut score = 0;
+    for board in 0..9 {
+        let weight = if board == 4 { 3 } else { 2 };
+        match game.local_winner(board) {
+            Some(winner) if winner == marker => score += 100 * weight,
+            Some(_) => score -= 100 * weight,
+            None if !game.is_open(board) => {}
+            None => {
+                let local = &game.boards[board];
+                for line in LINES {
+                    let owners = line.map(|cell| Some(local.cell(cell / 3, cell % 3)).filter(|&cell| cell != Cell::Empty));
+                    score += weight * line_score(owners, [0, 1, 10]);
+                }
+                match local.cell(1, 1) {
+                    Cell::Empty => {}
+                    centre if centre == marker => score += weight,
+                    _ => score -= weight,
+                }
+            }
+        }
+    }
+    for line in LINES {
+        // a drawn local board blocks every meta line through it
+        if line.iter().any(|&board| !game.is_open(board) && game.local_winner(board).is_none()) {
+            continue;
+        }
+        score += line_score(line.map(|board| game.local_winner(board)), [0, 50, 400]);
+    }
+    score
+}